@@ -0,0 +1,209 @@
+#!/usr/bin/env python3
+"""Regenerates characters.tsv, the table behind the launcher's character picker.
+
+Each line is `character<TAB>lowercase Unicode name<TAB>keywords`, with the keywords column left
+off when there are none. Emoji come first so they rank ahead of symbols sharing a name.
+
+Names come from the Unicode Character Database through Python's `unicodedata`, so the table
+follows the Unicode version of the interpreter that runs this (14.0 for the checked-in copy).
+The database is distributed under the Unicode License v3: https://www.unicode.org/license.txt
+The keywords below are Cantus's own.
+
+Usage: python3 assets/characters.py > assets/characters.tsv
+"""
+
+import sys
+import unicodedata
+
+# Blocks in table order, as inclusive codepoint ranges.
+GROUPS = [
+    # Emoticons and supplemental faces, hands and people.
+    [(0x1F600, 0x1F64F), (0x1F900, 0x1F9FF)],
+    # Pictographs, transport and the newer symbols.
+    [(0x1F300, 0x1F5FF), (0x1F680, 0x1F6FF), (0x1FA70, 0x1FAFF)],
+    # Miscellaneous symbols and dingbats.
+    [(0x2600, 0x27BF), (0x2B00, 0x2BFF)],
+    # Arrows, technical symbols and geometric shapes.
+    [(0x2190, 0x21FF), (0x2300, 0x23FF), (0x25A0, 0x25FF)],
+    # Mathematical operators.
+    [(0x2200, 0x22FF)],
+    # Letterlike symbols and number forms.
+    [(0x2100, 0x218F)],
+    # Currency.
+    [(0x20A0, 0x20CF)],
+    # Punctuation and enclosed alphanumerics.
+    [(0x2010, 0x206F), (0x2460, 0x24FF)],
+    # Latin-1 punctuation and signs.
+    [(0xA1, 0xBF), (0xD7, 0xD7), (0xF7, 0xF7)],
+]
+
+# Spaces, separators and invisible formatting characters have nothing to pick.
+SKIPPED_CATEGORIES = {"Cc", "Cf", "Cn", "Co", "Cs", "Zl", "Zp", "Zs"}
+
+KEYWORDS = {
+    "😀": "smile happy",
+    "😂": "lol laugh crying",
+    "😅": "nervous sweat",
+    "😉": "wink",
+    "😊": "smile happy blush",
+    "😍": "love crush",
+    "😎": "cool sunglasses",
+    "😏": "smirk",
+    "😘": "kiss love",
+    "😠": "angry mad",
+    "😡": "angry mad",
+    "😢": "sad tear",
+    "😭": "sad cry sob",
+    "😱": "scared shock",
+    "😴": "sleep tired zzz",
+    "🙂": "smile",
+    "🙃": "silly sarcasm",
+    "🙄": "eyeroll whatever",
+    "🙌": "hooray celebrate",
+    "🙏": "pray thanks please",
+    "🤔": "think hmm",
+    "🤖": "robot bot",
+    "🤗": "hug",
+    "🤞": "luck hope",
+    "🤣": "lol laugh rofl",
+    "🤦": "facepalm",
+    "🤩": "star struck wow",
+    "🤮": "sick vomit",
+    "🤯": "mind blown",
+    "🤷": "shrug whatever",
+    "🥳": "party celebrate",
+    "🥺": "please puppy eyes",
+    "🦀": "crab rust",
+    "🦄": "unicorn",
+    "🦊": "fox",
+    "🧠": "brain smart",
+    "🌈": "rainbow",
+    "🌍": "earth world globe",
+    "🌟": "star glow",
+    "🌧": "rain weather",
+    "🍎": "apple fruit",
+    "🍔": "burger food",
+    "🍕": "pizza food",
+    "🍷": "wine drink",
+    "🍺": "beer drink",
+    "🎁": "gift present",
+    "🎂": "birthday cake",
+    "🎉": "party tada celebrate",
+    "🎊": "party confetti",
+    "🎤": "microphone sing karaoke",
+    "🎧": "headphones music",
+    "🎮": "game controller gaming",
+    "🎲": "dice game",
+    "🎵": "music note song",
+    "🎶": "music notes song",
+    "🎸": "guitar music",
+    "🏆": "trophy win",
+    "🏠": "home house",
+    "🐍": "snake python",
+    "🐛": "bug",
+    "🐞": "ladybug bug",
+    "🐱": "cat kitten",
+    "🐶": "dog puppy",
+    "🐻": "bear",
+    "👀": "eyes look",
+    "👋": "wave hello bye",
+    "👌": "ok perfect",
+    "👍": "like yes approve ok",
+    "👎": "dislike no",
+    "👏": "clap applause",
+    "👻": "ghost",
+    "👽": "alien",
+    "💀": "dead skull",
+    "💔": "heartbreak",
+    "💡": "idea bulb",
+    "💩": "poop",
+    "💪": "strong muscle flex",
+    "💯": "hundred perfect score",
+    "💻": "laptop computer",
+    "📅": "calendar date",
+    "📌": "pin",
+    "📎": "paperclip attach",
+    "📧": "email mail",
+    "📱": "phone mobile",
+    "🔍": "search find magnifier",
+    "🔑": "key password",
+    "🔒": "lock secure",
+    "🔥": "fire lit hot",
+    "🚀": "rocket launch ship",
+    "🚗": "car",
+    "🚨": "alarm siren emergency",
+    "🚫": "forbidden no",
+    "🚲": "bike bicycle",
+    "☀": "sun sunny weather",
+    "☁": "cloud weather",
+    "★": "star",
+    "☆": "star",
+    "☕": "coffee tea",
+    "♪": "music note",
+    "⚠": "warning caution",
+    "⚡": "lightning zap power",
+    "⚽": "football soccer",
+    "✅": "done check yes",
+    "✈": "plane flight travel",
+    "✌": "peace victory",
+    "✓": "check tick",
+    "✔": "check tick",
+    "✗": "cross",
+    "✨": "sparkle shiny",
+    "❄": "snow cold",
+    "❌": "no wrong cross",
+    "❓": "question",
+    "❗": "important exclamation",
+    "❤": "love heart red",
+    "⭐": "star",
+    "←": "arrow left",
+    "↑": "arrow up",
+    "→": "arrow right",
+    "↓": "arrow down",
+    "↵": "enter return",
+    "⌘": "command cmd mac",
+    "⌛": "hourglass wait",
+    "⏎": "enter return",
+    "⏰": "alarm clock",
+    "∞": "infinity",
+    "≈": "approximately",
+    "≠": "not equal",
+    "≤": "less equal",
+    "≥": "greater equal",
+    "™": "trademark tm",
+    "€": "euro money",
+    "–": "dash en",
+    "—": "dash em",
+    "•": "bullet dot",
+    "…": "ellipsis dots",
+    "£": "pound money",
+    "¥": "yen money",
+    "§": "section",
+    "©": "copyright",
+    "®": "registered",
+    "°": "degree",
+    "±": "plus minus",
+    "¶": "paragraph pilcrow",
+    "×": "times multiply",
+    "÷": "divide",
+}
+
+
+def main():
+    seen = set()
+    for group in GROUPS:
+        for first, last in group:
+            for codepoint in range(first, last + 1):
+                character = chr(codepoint)
+                name = unicodedata.name(character, "")
+                if not name or character in seen or unicodedata.category(character) in SKIPPED_CATEGORIES:
+                    continue
+                seen.add(character)
+                fields = [character, name.lower()]
+                if character in KEYWORDS:
+                    fields.append(KEYWORDS[character])
+                sys.stdout.write("\t".join(fields) + "\n")
+
+
+if __name__ == "__main__":
+    main()
@@ -1,25 +1,35 @@
 use crate::render::{cpu::RenderState, launcher::LauncherState};
 use interaction::InteractionState;
-use music::{Enrichment, MusicBackend, PlaybackState};
+use music::{AudioTap, Enrichment, MusicBackend, PlaybackState};
 use platform::{Current as Platform, Platform as _};
 use std::{
     future::Future,
     io,
-    sync::mpsc::{self, Sender},
+    sync::{
+        Arc,
+        mpsc::{self, Sender},
+    },
     time::Duration,
 };
+use timers::Timers;
 use tokio::runtime::{Builder as RuntimeBuilder, Handle, Runtime};
 use tracing::{Level, level_filters::LevelFilter};
 use tracing_subscriber::{filter::Targets, fmt, layer::SubscriberExt, util::SubscriberInitExt};
 
+#[path = "cache.rs"]
+pub mod cache;
 #[path = "config.rs"]
 pub mod config;
+#[path = "http.rs"]
+pub mod http;
 #[path = "interaction.rs"]
 pub mod interaction;
 #[path = "music/mod.rs"]
 pub mod music;
 #[path = "platform/linux.rs"]
 pub mod platform;
+#[path = "timers.rs"]
+pub mod timers;
 
 pub(crate) type Update<T> = Box<dyn FnOnce(&mut T) + Send>;
 pub type AppUpdater = Sender<Update<CantusApp>>;
@@ -57,11 +67,13 @@ pub struct CantusApp {
     pub(crate) interaction: InteractionState,
     pub(crate) playback: PlaybackState,
     pub(crate) launcher: LauncherState,
+    pub(crate) timers: Timers,
     pub(crate) app_updates: mpsc::Receiver<Update<Self>>,
     pub(crate) config: config::Config,
     pub(crate) updater: AppUpdater,
     pub(crate) enrichment: Enrichment,
     pub(crate) music: MusicBackend,
+    pub(crate) audio: Arc<AudioTap>,
     _runtime: Runtime,
 }
 
@@ -79,19 +91,28 @@ impl Default for CantusApp {
             .build()
             .expect("failed to start Cantus async runtime");
         let background = Background::new(&runtime, &updater);
-        let enrichment = Enrichment::new(background.clone());
         let config = config::load();
+        let enrichment = Enrichment::new(background.clone(), &config);
         let music = MusicBackend::spotify(&config, &updater, &background);
         Platform::start_launcher_listener(&background, &updater);
+        Platform::start_volume_monitor(&background, &updater, &config.player_application);
+        Platform::start_media_monitor(&background, &updater, &config.player_application);
+        let audio = Arc::new(AudioTap::load(&config, &background));
+        Platform::start_playback_capture(&background, &updater, Arc::clone(&audio));
+        let timers = Timers::load(&background);
+        let mut launcher = LauncherState::new(&background, &enrichment.http, &config);
+        launcher.set_timers(&timers.active);
         Self {
             render: RenderState::default(),
             interaction: InteractionState::new(music.clone()),
             playback: PlaybackState::default(),
-            launcher: LauncherState::new(&background, &enrichment.http, config.search_providers.clone()),
+            launcher,
+            timers,
             app_updates,
             updater,
             enrichment,
             music,
+            audio,
             config,
             _runtime: runtime,
         }
@@ -0,0 +1,143 @@
+use crate::app::config;
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use std::{
+    fs::{self, File},
+    io,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use tokio::task::spawn_blocking;
+use tracing::warn;
+
+/// Files named by a stable hash of their key, written whole and trimmed to a size budget, least recently used first.
+pub struct DiskCache {
+    pub directory: &'static str,
+    pub max_bytes: u64,
+}
+
+impl DiskCache {
+    pub fn directory(&self) -> PathBuf {
+        config::directory().join(self.directory)
+    }
+
+    /// The file for `key`, named by its FNV-1a hash so names survive toolchain updates, then `suffix`.
+    pub fn path(&self, key: impl IntoIterator<Item = u8>, suffix: &str) -> PathBuf {
+        let hash = key
+            .into_iter()
+            .fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3));
+        self.directory().join(format!("{hash:016x}{suffix}"))
+    }
+
+    /// The contents of `path`, refreshing its age so eviction keeps recently used entries.
+    pub fn read(path: &Path) -> Option<Vec<u8>> {
+        let bytes = fs::read(path).ok()?;
+        let _ = File::options().append(true).open(path).and_then(|file| file.set_modified(SystemTime::now()));
+        Some(bytes)
+    }
+
+    /// Writes `bytes` to `path` through a partial file, so readers never see half an entry, then trims the cache.
+    ///
+    /// # Errors
+    /// Returns an error when the entry can't be written or an old one can't be removed.
+    pub fn store(&self, path: &Path, bytes: &[u8]) -> io::Result<()> {
+        fs::create_dir_all(self.directory())?;
+        let partial = path.with_extension("partial");
+        fs::write(&partial, bytes)?;
+        fs::rename(partial, path)?;
+        self.evict()
+    }
+
+    /// Removes every entry.
+    ///
+    /// # Errors
+    /// Returns an error when the directory exists but cannot be removed.
+    pub fn clear(&self) -> io::Result<()> {
+        match fs::remove_dir_all(self.directory()) {
+            Err(error) if error.kind() != io::ErrorKind::NotFound => Err(error),
+            _ => Ok(()),
+        }
+    }
+
+    fn evict(&self) -> io::Result<()> {
+        let mut entries = fs::read_dir(self.directory())?
+            .flatten()
+            // Partial files are other writers' entries still on their way in.
+            .filter(|entry| entry.path().extension().is_none_or(|extension| extension != "partial"))
+            .filter_map(|entry| {
+                let metadata = entry.metadata().ok()?;
+                Some((metadata.modified().ok()?, metadata.len(), entry.path()))
+            })
+            .collect::<Vec<_>>();
+        let mut total = entries.iter().map(|(_, size, _)| size).sum::<u64>();
+        entries.sort_unstable_by_key(|(modified, ..)| *modified);
+        for (_, size, path) in entries {
+            if total <= self.max_bytes {
+                break;
+            }
+            // Another store may have evicted it already.
+            match fs::remove_file(path) {
+                Err(error) if error.kind() != io::ErrorKind::NotFound => return Err(error),
+                _ => total -= size,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Answers from an online source stored as one JSON file per track, so they outlive restarts.
+pub struct TrackCache {
+    pub directory: &'static str,
+    /// How long an answer is kept, so corrections made upstream eventually show up.
+    pub found_expiry: Duration,
+    /// How long a track the source had nothing for is remembered before it is asked again.
+    pub missing_expiry: Duration,
+}
+
+#[derive(Deserialize, Serialize)]
+struct TrackEntry<T> {
+    fetched_at: u64,
+    value: Option<T>,
+}
+
+impl TrackCache {
+    /// The unexpired answer stored for `uri`, holding `None` when the source had nothing for it.
+    pub async fn read<T: DeserializeOwned + Send + 'static>(&self, uri: &str) -> Option<Option<T>> {
+        let path = self.path(uri);
+        let entry = spawn_blocking(move || fs::read(path).ok().and_then(|bytes| serde_json::from_slice::<TrackEntry<T>>(&bytes).ok()))
+            .await
+            .ok()
+            .flatten()?;
+        let expiry = if entry.value.is_some() { self.found_expiry } else { self.missing_expiry };
+        (unix_now().saturating_sub(entry.fetched_at) < expiry.as_secs()).then_some(entry.value)
+    }
+
+    /// Stores the answer for `uri` in the background.
+    pub fn store<T: Serialize>(&self, uri: &str, value: Option<&T>) {
+        let entry = TrackEntry { fetched_at: unix_now(), value };
+        let Ok(bytes) = serde_json::to_vec(&entry).inspect_err(|error| warn!(%error, directory = self.directory, "Failed to encode cache entry")) else {
+            return;
+        };
+        let path = self.path(uri);
+        spawn_blocking(move || {
+            if let Err(error) = write(&path, &bytes) {
+                warn!(%error, ?path, "Failed to write cache entry");
+            }
+        });
+    }
+
+    fn path(&self, uri: &str) -> PathBuf {
+        let name = uri.chars().map(|c| if c.is_ascii_alphanumeric() { c } else { '_' }).collect::<String>();
+        config::directory().join(self.directory).join(name + ".json")
+    }
+}
+
+fn write(path: &Path, bytes: &[u8]) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, bytes)
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs()
+}
@@ -30,6 +30,14 @@ pub struct Config {
     pub ratings_enabled: bool,
     /// Whether to show synchronized lyrics.
     pub lyrics_enabled: bool,
+    /// Lyrics sources, tried in order until one has timed lyrics for the track: `local` `.lrc` files, `binimum`, `lrclib` or `spotify`.
+    pub lyrics_providers: Vec<LyricsProvider>,
+    /// Directories searched, a few levels deep, for `Artist - Title.lrc` or `Title.lrc` files.
+    pub lyrics_directories: Vec<String>,
+    /// Whether to show a `translation` or `romanization` row beneath lyrics that include one.
+    pub lyrics_annotation: LyricsAnnotation,
+    /// Application name or process binary of the music player, whose own audio stream scrolling a track adjusts. Media from other MPRIS players shows as an interruption at the playhead.
+    pub player_application: String,
 
     /// Whether to show the weather and calendar module.
     pub tempestas_enabled: bool,
@@ -39,8 +47,105 @@ pub struct Config {
     /// Whether to show the system status module.
     pub status_enabled: bool,
 
+    /// Where to draw a detailed audio spectrum: `off`, across the playing track's `pill`, or full width along the lyrics `strip`.
+    pub visualiser: Visualiser,
+    /// Number of logarithmically spaced frequency bands in the visualiser, up to 128.
+    pub visualiser_bands: u32,
+    /// Seconds the visualiser takes to settle after a change; higher is smoother.
+    pub visualiser_smoothing: f32,
+    /// Whether to also trace the waveform through the visualiser.
+    pub visualiser_waveform: bool,
+
+    /// How colours are picked from album art.
+    pub palette: PaletteOptions,
+
     /// Web search providers; the first is the unprefixed fallback.
     pub search_providers: Vec<SearchProvider>,
+    /// Commands whose printed rows join the launcher's results.
+    pub launcher_commands: Vec<CommandProvider>,
+    /// Command that terminal apps run in, with `{cmd}` standing for the app's command, such as `foot -e {cmd}`; `$TERMINAL` or the first common terminal installed is used when unset.
+    pub terminal: Option<String>,
+    /// Whether the launcher starts each app in its own systemd user scope, so it is tracked apart from Cantus.
+    pub systemd_scopes: bool,
+    /// Copied texts kept for the launcher's `!clip` mode; 0 stops watching the clipboard.
+    pub clipboard_history: u32,
+    /// Whether the clipboard history is saved across restarts.
+    pub clipboard_history_persist: bool,
+    /// Outline font drawing emoji and symbols the built-in font lacks; a system emoji font is used when unset.
+    pub fallback_font: Option<String>,
+
+    /// Base URLs of the online services used, for self-hosted mirrors or local stand-ins; an empty URL disables that service.
+    pub services: Services,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug, Deserialize, Serialize)]
+#[cfg_attr(all(debug_assertions, feature = "generate-nix"), derive(schemars::JsonSchema))]
+#[serde(default)]
+pub struct PaletteOptions {
+    /// How far, 0 to 255, a pixel's largest sRGB channel must exceed its smallest for it to count as colourful; art without any uses every pixel.
+    pub min_spread: u8,
+    /// Minimum LCh chroma, around 0 to 130, a colourful pixel must also have.
+    pub min_chroma: f32,
+    /// Lightness range, within 0 to 100, of the pixels measured.
+    pub lightness: [f32; 2],
+    /// Whether to merge similar hues and fill the palette with analogous colours.
+    pub harmony: bool,
+}
+
+impl Default for PaletteOptions {
+    fn default() -> Self {
+        Self {
+            min_spread: 30,
+            min_chroma: 0.0,
+            lightness: [0.0, 100.0],
+            harmony: true,
+        }
+    }
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+#[cfg_attr(all(debug_assertions, feature = "generate-nix"), derive(schemars::JsonSchema))]
+#[serde(default)]
+pub struct Services {
+    /// ReccoBeats API, for audio features.
+    pub reccobeats: String,
+    /// binimum lyrics API, for word-timed lyrics.
+    pub binimum: String,
+    /// LRCLIB-compatible lyrics server.
+    pub lrclib: String,
+    /// Open-Meteo forecast API.
+    pub open_meteo: String,
+    /// Open-Meteo geocoding API, which places each timezone's weather.
+    pub open_meteo_geocoding: String,
+    /// ExchangeRate-API, for currency conversions in the launcher.
+    pub exchange_rates: String,
+    /// MusicBrainz-compatible API, for genres, release years and labels; off unless set, such as to `https://musicbrainz.org/ws/2`.
+    pub musicbrainz: String,
+    /// Requests per second made to `musicbrainz`; the public server blocks clients above one.
+    pub musicbrainz_requests_per_second: f32,
+}
+
+impl Default for Services {
+    fn default() -> Self {
+        Self {
+            reccobeats: "https://api.reccobeats.com/v1".into(),
+            binimum: "https://lyrics-api.binimum.org".into(),
+            lrclib: "https://lrclib.net".into(),
+            open_meteo: "https://api.open-meteo.com/v1".into(),
+            open_meteo_geocoding: "https://geocoding-api.open-meteo.com/v1".into(),
+            exchange_rates: "https://open.er-api.com/v6".into(),
+            musicbrainz: String::new(),
+            musicbrainz_requests_per_second: 1.0,
+        }
+    }
+}
+
+impl Services {
+    /// `base` without its trailing slash, or `None` when the service is disabled.
+    pub fn endpoint(base: &str) -> Option<String> {
+        let base = base.trim().trim_end_matches('/');
+        (!base.is_empty()).then(|| base.to_owned())
+    }
 }
 
 #[derive(Clone, Deserialize, Serialize)]
@@ -54,6 +159,62 @@ pub struct SearchProvider {
     pub alias: String,
 }
 
+/// A launcher result source run as a shell command for each query, which it receives as `$1`.
+///
+/// It prints JSON rows, as an array or one object per line, each with a `title` and an `action` command and
+/// optionally a `detail`, an `icon` path, and an `alternate` command run by shift+enter, named by `alternate_label`.
+#[derive(Clone, Deserialize, Serialize)]
+#[cfg_attr(all(debug_assertions, feature = "generate-nix"), derive(schemars::JsonSchema))]
+#[serde(default)]
+pub struct CommandProvider {
+    /// Name used in the launch history and logs, such as `ssh`.
+    pub name: String,
+    pub command: String,
+    /// Milliseconds the command may run before its rows are dropped.
+    pub timeout_ms: u32,
+}
+
+impl Default for CommandProvider {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            command: String::new(),
+            timeout_ms: 1000,
+        }
+    }
+}
+
+/// A source of timed lyrics.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[cfg_attr(all(debug_assertions, feature = "generate-nix"), derive(schemars::JsonSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum LyricsProvider {
+    Local,
+    Binimum,
+    Lrclib,
+    Spotify,
+}
+
+/// A companion row for lyrics in another language or script.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Deserialize, Serialize)]
+#[cfg_attr(all(debug_assertions, feature = "generate-nix"), derive(schemars::JsonSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum LyricsAnnotation {
+    Off,
+    Translation,
+    Romanization,
+}
+
+/// Where the audio spectrum visualiser is drawn.
+#[derive(Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[cfg_attr(all(debug_assertions, feature = "generate-nix"), derive(schemars::JsonSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum Visualiser {
+    Off,
+    Pill,
+    Strip,
+}
+
 #[derive(Clone, Copy, Deserialize, Serialize)]
 #[cfg_attr(all(debug_assertions, feature = "generate-nix"), derive(schemars::JsonSchema))]
 #[serde(rename_all = "lowercase")]
@@ -88,11 +249,27 @@ impl Default for Config {
             playlists: ArrayVec::new(),
             ratings_enabled: false,
             lyrics_enabled: true,
+            lyrics_providers: vec![LyricsProvider::Local, LyricsProvider::Binimum, LyricsProvider::Lrclib, LyricsProvider::Spotify],
+            lyrics_directories: vec!["~/Music".into(), "~/.lyrics".into()],
+            lyrics_annotation: LyricsAnnotation::Off,
+            player_application: "spotify".into(),
+            visualiser: Visualiser::Off,
+            visualiser_bands: 64,
+            visualiser_smoothing: 0.12,
+            visualiser_waveform: false,
+            palette: PaletteOptions::default(),
             search_providers: vec![SearchProvider {
                 name: "DuckDuckGo".into(),
                 url: "https://duckduckgo.com/?q={searchTerms}".into(),
                 alias: "!ddg".into(),
             }],
+            launcher_commands: Vec::new(),
+            terminal: None,
+            systemd_scopes: false,
+            clipboard_history: 50,
+            clipboard_history_persist: false,
+            fallback_font: None,
+            services: Services::default(),
         }
     }
 }
@@ -117,6 +294,14 @@ pub fn directory() -> PathBuf {
         .join("cantus")
 }
 
+/// Expands a leading `~/` to the home directory.
+pub fn expand_path(path: &str) -> PathBuf {
+    match path.strip_prefix("~/") {
+        Some(rest) => env::var_os("HOME").map(PathBuf::from).unwrap_or_default().join(rest),
+        None => PathBuf::from(path),
+    }
+}
+
 pub fn load() -> Config {
     let path = directory().join("cantus.toml");
     fs::read_to_string(&path)
@@ -177,6 +362,7 @@ pub mod nix_options {
             None => "lib.types.nullOr (lib.types.str)".into(),
             Some("string") => "lib.types.str".into(),
             Some("number") => "lib.types.number".into(),
+            Some("integer") => "lib.types.int".into(),
             Some("boolean") => "lib.types.bool".into(),
             Some("array") => {
                 let list = format!("lib.types.listOf ({})", nix_type(&schema["items"]));
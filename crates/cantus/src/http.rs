@@ -0,0 +1,256 @@
+use crate::app::cache::DiskCache;
+use reqwest::{
+    Client, IntoUrl, RequestBuilder, StatusCode,
+    header::{self, HeaderMap, HeaderValue},
+};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use std::{
+    error, fmt, io,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use tokio::task::spawn_blocking;
+use tracing::warn;
+
+const CACHE: DiskCache = DiskCache {
+    directory: "http",
+    max_bytes: 48 * 1024 * 1024,
+};
+
+/// The HTTP client for every Cantus fetch. GET responses are cached on disk as `Cache-Control` allows,
+/// and stale entries are revalidated with their `ETag` or `Last-Modified` before being reused.
+#[derive(Clone)]
+pub struct HttpClient {
+    client: Client,
+}
+
+impl Default for HttpClient {
+    fn default() -> Self {
+        Self {
+            client: Client::builder()
+                .user_agent(concat!("Cantus/", env!("CARGO_PKG_VERSION")))
+                .timeout(Duration::from_secs(15))
+                .build()
+                .expect("failed to construct HTTP client"),
+        }
+    }
+}
+
+impl HttpClient {
+    pub fn get(&self, url: impl IntoUrl) -> Request<'_> {
+        Request {
+            client: &self.client,
+            builder: self.client.get(url),
+        }
+    }
+}
+
+pub struct Request<'a> {
+    client: &'a Client,
+    builder: RequestBuilder,
+}
+
+impl Request<'_> {
+    #[must_use]
+    pub fn query<T: Serialize + ?Sized>(self, query: &T) -> Self {
+        Self {
+            client: self.client,
+            builder: self.builder.query(query),
+        }
+    }
+
+    /// Answers from the cache while the stored response is fresh, otherwise asks the server,
+    /// conditionally when a stale response can be revalidated. A stale response is also reused when the server
+    /// can't be reached.
+    ///
+    /// # Errors
+    /// Returns an error when the request cannot be sent and nothing is cached, or when its body cannot be read.
+    pub async fn send(self) -> Result<Response, Error> {
+        let mut request = self.builder.build()?;
+        let url = request.url().as_str().to_owned();
+        let key = url.clone();
+        let cached = spawn_blocking(move || load(&key)).await.ok().flatten();
+        let now = unix_now();
+        if let Some(entry) = &cached {
+            if entry.header.fresh_until > now {
+                return Ok(Response::ok(entry.body.clone()));
+            }
+            let headers = request.headers_mut();
+            let validators = [(header::IF_NONE_MATCH, &entry.header.etag), (header::IF_MODIFIED_SINCE, &entry.header.last_modified)];
+            for (name, value) in validators {
+                if let Some(value) = value.as_deref().and_then(|value| HeaderValue::from_str(value).ok()) {
+                    headers.insert(name, value);
+                }
+            }
+        }
+
+        let response = match self.client.execute(request).await {
+            Ok(response) => response,
+            // A stale response beats none while the server can't be reached.
+            Err(error) => return cached.map(|entry| Response::ok(entry.body)).ok_or_else(|| error.into()),
+        };
+        let status = response.status();
+        let lifetime = lifetime(response.headers());
+        if status == StatusCode::NOT_MODIFIED
+            && let Some(mut entry) = cached
+        {
+            entry.header.fresh_until = now + lifetime.unwrap_or_default();
+            let body = entry.body.clone();
+            spawn_blocking(move || store(&entry));
+            return Ok(Response::ok(body));
+        }
+
+        let header = EntryHeader {
+            url,
+            etag: header_string(response.headers(), header::ETAG),
+            last_modified: header_string(response.headers(), header::LAST_MODIFIED),
+            fresh_until: now + lifetime.unwrap_or_default(),
+        };
+        let body = response.bytes().await?.to_vec();
+        // Without a lifetime or validators a stored response could never be reused.
+        let reusable = header.fresh_until > now || header.etag.is_some() || header.last_modified.is_some();
+        if status == StatusCode::OK && lifetime.is_some() && reusable {
+            let entry = Entry { header, body: body.clone() };
+            spawn_blocking(move || store(&entry));
+        }
+        Ok(Response { status, body })
+    }
+}
+
+/// A response body, either fresh from the server or reused from the cache.
+pub struct Response {
+    status: StatusCode,
+    body: Vec<u8>,
+}
+
+impl Response {
+    const fn ok(body: Vec<u8>) -> Self {
+        Self { status: StatusCode::OK, body }
+    }
+
+    pub const fn status(&self) -> StatusCode {
+        self.status
+    }
+
+    /// # Errors
+    /// Returns [`Error::Status`] for client and server error statuses.
+    pub fn error_for_status(self) -> Result<Self, Error> {
+        if self.status.is_client_error() || self.status.is_server_error() {
+            return Err(Error::Status(self.status));
+        }
+        Ok(self)
+    }
+
+    /// # Errors
+    /// Returns [`Error::Decode`] when the body isn't valid JSON for `T`.
+    pub fn json<T: DeserializeOwned>(&self) -> Result<T, Error> {
+        Ok(serde_json::from_slice(&self.body)?)
+    }
+
+    pub fn text(&self) -> String {
+        String::from_utf8_lossy(&self.body).into_owned()
+    }
+
+    pub fn bytes(self) -> Vec<u8> {
+        self.body
+    }
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Request(reqwest::Error),
+    Status(StatusCode),
+    Decode(serde_json::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Request(error) => error.fmt(formatter),
+            Self::Status(status) => write!(formatter, "HTTP status {status}"),
+            Self::Decode(error) => write!(formatter, "invalid response: {error}"),
+        }
+    }
+}
+
+impl error::Error for Error {}
+
+impl From<reqwest::Error> for Error {
+    fn from(error: reqwest::Error) -> Self {
+        Self::Request(error)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(error: serde_json::Error) -> Self {
+        Self::Decode(error)
+    }
+}
+
+/// Removes every cached response.
+///
+/// # Errors
+/// Returns an error when the cache directory exists but cannot be removed.
+pub fn clear() -> io::Result<()> {
+    CACHE.clear()
+}
+
+/// Stored ahead of the body as one JSON line.
+#[derive(Deserialize, Serialize)]
+struct EntryHeader {
+    url: String,
+    etag: Option<String>,
+    last_modified: Option<String>,
+    /// Unix seconds until which the body is reused without asking the server.
+    fresh_until: u64,
+}
+
+struct Entry {
+    header: EntryHeader,
+    body: Vec<u8>,
+}
+
+/// Seconds a response may be reused without revalidation, or `None` when it must not be stored.
+fn lifetime(headers: &HeaderMap) -> Option<u64> {
+    let (mut max_age, mut revalidate) = (0, false);
+    let directives = headers.get_all(header::CACHE_CONTROL).iter().filter_map(|value| value.to_str().ok());
+    for directive in directives.flat_map(|value| value.split(',')).map(str::trim) {
+        let (name, value) = directive.split_once('=').unwrap_or((directive, ""));
+        match name.to_ascii_lowercase().as_str() {
+            "no-store" => return None,
+            "no-cache" => revalidate = true,
+            "max-age" => max_age = value.trim_matches('"').parse().unwrap_or(0),
+            _ => {}
+        }
+    }
+    let age = header_string(headers, header::AGE).and_then(|age| age.parse::<u64>().ok()).unwrap_or(0);
+    Some(if revalidate { 0 } else { max_age.saturating_sub(age) })
+}
+
+fn header_string(headers: &HeaderMap, name: header::HeaderName) -> Option<String> {
+    headers.get(name)?.to_str().ok().map(str::to_owned)
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs()
+}
+
+/// The stored response for `url`.
+fn load(url: &str) -> Option<Entry> {
+    let bytes = DiskCache::read(&CACHE.path(url.bytes(), ""))?;
+    let (header, body) = bytes.split_at(bytes.iter().position(|&byte| byte == b'\n')?);
+    let header = serde_json::from_slice::<EntryHeader>(header).ok().filter(|header| header.url == url)?;
+    Some(Entry { header, body: body[1..].to_vec() })
+}
+
+fn store(entry: &Entry) {
+    if let Err(error) = write(entry) {
+        warn!(%error, url = entry.header.url, "Failed to cache HTTP response");
+    }
+}
+
+fn write(entry: &Entry) -> io::Result<()> {
+    let mut bytes = serde_json::to_vec(&entry.header)?;
+    bytes.push(b'\n');
+    bytes.extend_from_slice(&entry.body);
+    CACHE.store(&CACHE.path(entry.header.url.bytes(), ""), &bytes)
+}
@@ -29,6 +29,7 @@ pub struct InteractionState {
     pub pointer: Vec2,
     event: Option<PointerEvent>,
     scroll: i32,
+    middle_click: bool,
     hover_claimed: bool,
     pulse: Option<Vec2>,
     regions: Vec<Rect>,
@@ -78,6 +79,7 @@ impl InteractionState {
             pointer: Vec2::ZERO,
             event: None,
             scroll: 0,
+            middle_click: false,
             hover_claimed: false,
             pulse: None,
             regions: Vec::new(),
@@ -104,6 +106,11 @@ impl InteractionState {
         if rect.contains(self.pointer) { mem::take(&mut self.scroll) } else { 0 }
     }
 
+    /// Takes this frame's middle click if it landed in `rect`.
+    pub fn middle_clicked(&mut self, rect: Rect) -> bool {
+        rect.contains(self.pointer) && mem::take(&mut self.middle_click)
+    }
+
     pub fn contains(&self, rect: Rect) -> bool {
         self.state != Pointer::Outside && rect.contains(self.pointer)
     }
@@ -143,6 +150,7 @@ impl InteractionState {
         }
         self.event = None;
         self.scroll = 0;
+        self.middle_click = false;
         pulse
     }
 
@@ -190,6 +198,10 @@ impl InteractionState {
         self.scroll = direction;
     }
 
+    pub const fn middle_press(&mut self) {
+        self.middle_click = true;
+    }
+
     pub const fn claim_hover(&mut self) {
         self.hover_claimed = true;
     }
@@ -234,6 +246,10 @@ impl InteractionState {
         self.rate_burst = Some(self.pointer);
     }
 
+    pub fn seek_to(&self, position_ms: u32) {
+        self.music.command(PlaybackCommand::Seek(position_ms));
+    }
+
     pub fn seek(&self, timeline: &Timeline, clicked_index: usize, clicked_duration_ms: u32, fraction: f32) {
         let skip_count = clicked_index.abs_diff(timeline.index);
         if skip_count == 0 {
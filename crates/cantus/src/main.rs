@@ -1,12 +1,20 @@
 use cantus::app::{
+    http,
     platform::{Current as Platform, Platform as _},
     run,
 };
-use std::env;
+use std::{env, process};
 
 fn main() {
     if env::args().any(|arg| arg == "--launcher") {
         Platform::trigger_launcher();
     }
+    if env::args().any(|arg| arg == "--clear-http-cache") {
+        if let Err(error) = http::clear() {
+            eprintln!("Failed to clear the HTTP cache: {error}");
+            process::exit(1);
+        }
+        return;
+    }
     run();
 }
@@ -0,0 +1,377 @@
+use crate::{
+    app::{
+        Background,
+        config::{self, Config, Visualiser},
+    },
+    render::{
+        status::AUDIO_SPECTRUM_BANDS,
+        track::AudioFeatures,
+        visualiser::{MAX_BANDS, WAVEFORM_POINTS},
+    },
+};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, VecDeque},
+    fs,
+    sync::atomic::{AtomicU32, Ordering},
+    time::{Instant, SystemTime, UNIX_EPOCH},
+};
+use tracing::warn;
+
+const ESTIMATES_FILE: &str = "cantus_audio_estimates.json";
+/// Estimates kept on disk; the oldest make way for new tracks.
+const MAX_ESTIMATES: usize = 2000;
+/// Audio heard of a track before its features are estimated.
+const LISTEN_SECONDS: f32 = 30.0;
+/// Windows quieter than this are gaps or pauses, not part of the track.
+const SILENCE_RMS: f32 = 1e-4;
+const TEMPO_RANGE: (f32, f32) = (60.0, 200.0);
+/// Tempo that octave-ambiguous estimates lean towards.
+const TEMPO_PRIOR: f32 = 120.0;
+/// Recent audio the beat tracker searches for a steady pulse.
+const BEAT_HISTORY_SECONDS: f32 = 6.0;
+/// Windows between beat re-estimates.
+const BEAT_INTERVAL: u32 = 4;
+
+/// Captured playback audio, shared between the capture thread and the app.
+pub struct AudioTap {
+    /// Latest level of each status spectrum band, as `f32` bits.
+    pub spectrum: [AtomicU32; AUDIO_SPECTRUM_BANDS],
+    /// Latest level of each visualiser band, as `f32` bits; empty while the visualiser is off.
+    pub detail: Box<[AtomicU32]>,
+    /// Latest window downsampled to visualiser points, as `f32` bits; empty unless traced.
+    pub waveform: Box<[AtomicU32]>,
+    /// Detected beat period in seconds, as `f32` bits.
+    beat_period: AtomicU32,
+    /// When the latest beat landed, in seconds after `epoch`, as `f32` bits.
+    beat_at: AtomicU32,
+    /// Confidence in the detected beat, as `f32` bits; zero without a steady pulse.
+    beat_strength: AtomicU32,
+    epoch: Instant,
+    beats: Mutex<BeatTracker>,
+    analysis: Mutex<Analysis>,
+    /// Finished estimates by track URI, persisted across runs.
+    estimates: Mutex<HashMap<String, Estimate>>,
+    background: Background,
+}
+
+#[derive(Clone, Copy, Deserialize, Serialize)]
+struct Estimate {
+    features: AudioFeatures,
+    /// Unix seconds when the track was heard.
+    heard_at: u64,
+}
+
+impl AudioTap {
+    pub fn load(config: &Config, background: &Background) -> Self {
+        let visualiser = config.visualiser != Visualiser::Off;
+        let atomics = |count: usize| (0..count).map(|_| AtomicU32::default()).collect();
+        let estimates = fs::read(config::directory().join(ESTIMATES_FILE))
+            .ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).inspect_err(|error| warn!(%error, "Failed to parse audio estimates")).ok())
+            .unwrap_or_default();
+        Self {
+            spectrum: Default::default(),
+            detail: atomics(if visualiser { (config.visualiser_bands as usize).clamp(1, MAX_BANDS) } else { 0 }),
+            waveform: atomics(if visualiser && config.visualiser_waveform { WAVEFORM_POINTS } else { 0 }),
+            beat_period: AtomicU32::default(),
+            beat_at: AtomicU32::default(),
+            beat_strength: AtomicU32::default(),
+            epoch: Instant::now(),
+            beats: Mutex::default(),
+            analysis: Mutex::default(),
+            estimates: Mutex::new(estimates),
+            background: background.clone(),
+        }
+    }
+
+    /// Clears the spectrum and beat while nothing is being captured.
+    pub fn silence(&self) {
+        for level in self.spectrum.iter().chain(&self.detail).chain(&self.waveform) {
+            level.store(0.0f32.to_bits(), Ordering::Relaxed);
+        }
+        self.beat_strength.store(0.0f32.to_bits(), Ordering::Relaxed);
+        *self.beats.lock() = BeatTracker::default();
+    }
+
+    /// Progress from the latest detected beat towards the next, in 0..1, and the confidence in it.
+    pub fn beat(&self) -> (f32, f32) {
+        let load = |value: &AtomicU32| f32::from_bits(value.load(Ordering::Relaxed));
+        let (period, strength) = (load(&self.beat_period), load(&self.beat_strength));
+        if period <= 0.0 || strength <= 0.0 {
+            return (0.0, 0.0);
+        }
+        let since = self.epoch.elapsed().as_secs_f32() - load(&self.beat_at);
+        ((since / period).rem_euclid(1.0), strength)
+    }
+
+    /// Follows the track being heard, `None` while nothing plays; a new track restarts the analysis.
+    pub fn listen(&self, track: Option<&str>) {
+        self.analysis.lock().listen(track);
+    }
+
+    pub fn estimate(&self, uri: &str) -> Option<AudioFeatures> {
+        self.estimates.lock().get(uri).map(|estimate| estimate.features)
+    }
+
+    /// Feeds one captured window, given its time-domain RMS and spectrum magnitudes.
+    /// Returns the track's estimate once enough of it has been heard, saving it in the background.
+    pub fn hear(&self, rms: f32, magnitudes: impl IntoIterator<Item = f32>, seconds: f32) -> Option<(String, AudioFeatures)> {
+        let mut beats = self.beats.lock();
+        let flux = beats.flux(magnitudes);
+        if let Some(beat) = beats.track(flux, seconds) {
+            let beat_at = self.epoch.elapsed().as_secs_f32() - beat.since;
+            self.beat_period.store(beat.period.to_bits(), Ordering::Relaxed);
+            self.beat_at.store(beat_at.to_bits(), Ordering::Relaxed);
+            self.beat_strength.store(beat.strength.to_bits(), Ordering::Relaxed);
+        }
+        drop(beats);
+
+        let (uri, features) = self.analysis.lock().push(rms, flux, seconds)?;
+        let heard_at = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+        let mut estimates = self.estimates.lock();
+        estimates.insert(uri.clone(), Estimate { features, heard_at });
+        if estimates.len() > MAX_ESTIMATES
+            && let Some(oldest) = estimates.iter().min_by_key(|(_, estimate)| estimate.heard_at).map(|(uri, _)| uri.clone())
+        {
+            estimates.remove(&oldest);
+        }
+        let snapshot = estimates.clone();
+        drop(estimates);
+        self.background.run(move || match serde_json::to_vec(&snapshot) {
+            Ok(contents) => {
+                if let Err(error) = fs::write(config::directory().join(ESTIMATES_FILE), contents) {
+                    warn!(%error, "Failed to save audio estimates");
+                }
+            }
+            Err(error) => warn!(%error, "Failed to encode audio estimates"),
+        });
+        Some((uri, features))
+    }
+}
+
+/// Running onset and loudness statistics for the track currently playing.
+#[derive(Default)]
+struct Analysis {
+    track: Option<String>,
+    playing: bool,
+    /// Set once the current track's estimate has been made.
+    finished: bool,
+    window_seconds: f32,
+    /// Spectral flux per window, relative to the window's total magnitude.
+    onsets: Vec<f32>,
+    power: f32,
+}
+
+impl Analysis {
+    fn listen(&mut self, track: Option<&str>) {
+        if let Some(uri) = track
+            && self.track.as_deref() != Some(uri)
+        {
+            *self = Self {
+                track: Some(uri.to_owned()),
+                ..Self::default()
+            };
+        }
+        self.playing = track.is_some();
+    }
+
+    fn push(&mut self, rms: f32, flux: f32, seconds: f32) -> Option<(String, AudioFeatures)> {
+        if !self.playing || self.finished || rms < SILENCE_RMS {
+            return None;
+        }
+        self.onsets.push(flux);
+        self.power += rms * rms;
+        self.window_seconds = seconds;
+        if self.onsets.len() as f32 * seconds < LISTEN_SECONDS {
+            return None;
+        }
+        self.finished = true;
+        Some((self.track.clone()?, self.estimate()))
+    }
+
+    /// Danceability from how strongly the tempo repeats, energy from loudness and onset density.
+    fn estimate(&self) -> AudioFeatures {
+        let count = self.onsets.len() as f32;
+        let mean = self.onsets.iter().sum::<f32>() / count;
+        let (tempo, clarity) = periodicity(&self.onsets, self.window_seconds);
+        let loudness = 10.0 * (self.power / count).max(f32::EPSILON).log10();
+        let level = ((loudness + 40.0) / 30.0).clamp(0.0, 1.0);
+        AudioFeatures {
+            energy: level * 0.6 + (mean * 4.0).clamp(0.0, 1.0) * 0.4,
+            danceability: clarity * 2.0,
+            tempo,
+            loudness,
+            ..AudioFeatures::default()
+        }
+        .normalized()
+    }
+}
+
+struct Beat {
+    period: f32,
+    /// Seconds since the latest beat, at the newest window.
+    since: f32,
+    strength: f32,
+}
+
+/// Follows the pulse of whatever is playing, independent of track changes.
+#[derive(Default)]
+struct BeatTracker {
+    previous: Vec<f32>,
+    /// Recent spectral flux, oldest first.
+    onsets: VecDeque<f32>,
+    windows: u32,
+}
+
+impl BeatTracker {
+    /// Positive spectral change since the previous window, relative to the window's total magnitude.
+    fn flux(&mut self, magnitudes: impl IntoIterator<Item = f32>) -> f32 {
+        let magnitudes = magnitudes.into_iter().collect::<Vec<_>>();
+        let total = magnitudes.iter().sum::<f32>().max(f32::EPSILON);
+        let flux = if self.previous.len() == magnitudes.len() {
+            magnitudes.iter().zip(&self.previous).map(|(current, previous)| (current - previous).max(0.0)).sum::<f32>() / total
+        } else {
+            0.0
+        };
+        self.previous = magnitudes;
+        flux
+    }
+
+    /// Re-estimates the beat every [`BEAT_INTERVAL`] windows once enough history is held.
+    fn track(&mut self, flux: f32, seconds: f32) -> Option<Beat> {
+        let capacity = (BEAT_HISTORY_SECONDS / seconds) as usize;
+        if self.onsets.len() == capacity {
+            self.onsets.pop_front();
+        }
+        self.onsets.push_back(flux);
+        self.windows = self.windows.wrapping_add(1);
+        if self.onsets.len() < capacity / 2 || !self.windows.is_multiple_of(BEAT_INTERVAL) {
+            return None;
+        }
+
+        let onsets = self.onsets.make_contiguous();
+        let (tempo, clarity) = periodicity(onsets, seconds);
+        let period = 60.0 / tempo / seconds;
+        // Fold the history onto one period, newest window first; the strongest slot is the beat.
+        let mut folded = vec![0.0; period.round().max(1.0) as usize];
+        for (age, onset) in onsets.iter().rev().enumerate() {
+            let slot = (age as f32 % period) as usize;
+            if let Some(total) = folded.get_mut(slot) {
+                *total += onset;
+            }
+        }
+        let since = folded.iter().enumerate().max_by(|a, b| a.1.total_cmp(b.1)).map_or(0, |(slot, _)| slot);
+        Some(Beat {
+            period: period * seconds,
+            since: since as f32 * seconds,
+            strength: (clarity * 2.0).clamp(0.0, 1.0),
+        })
+    }
+}
+
+/// Dominant tempo in BPM of an onset envelope sampled every `seconds`, found by autocorrelation
+/// weighted towards [`TEMPO_PRIOR`], with how strongly that period repeats.
+fn periodicity(onsets: &[f32], seconds: f32) -> (f32, f32) {
+    let mean = onsets.iter().sum::<f32>() / onsets.len().max(1) as f32;
+    let envelope = onsets.iter().map(|onset| onset - mean).collect::<Vec<_>>();
+    let correlation = |lag: usize| envelope.iter().zip(&envelope[lag..]).map(|(a, b)| a * b).sum::<f32>();
+    let zero = correlation(0).max(f32::EPSILON);
+
+    let rate = 1.0 / seconds;
+    let lags = (60.0 * rate / TEMPO_RANGE.1).ceil() as usize..=(60.0 * rate / TEMPO_RANGE.0).floor() as usize;
+    lags.filter(|&lag| lag > 0 && lag < envelope.len())
+        .map(|lag| {
+            let bpm = 60.0 * rate / lag as f32;
+            let weight = (-0.5 * (bpm / TEMPO_PRIOR).log2().powi(2)).exp();
+            (bpm, (correlation(lag) / zero).max(0.0) * weight)
+        })
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+        .unwrap_or((TEMPO_PRIOR, 0.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Window length that puts 120 BPM at exactly 50 windows per beat.
+    const WINDOW: f32 = 0.01;
+
+    /// An onset envelope with a spike every `period` windows.
+    fn pulse(windows: usize, period: usize) -> Vec<f32> {
+        (0..windows).map(|window| if window % period == 0 { 1.0 } else { 0.0 }).collect()
+    }
+
+    #[test]
+    fn flux_counts_only_rising_magnitudes() {
+        let mut beats = BeatTracker::default();
+        assert!(beats.flux([1.0, 1.0]).abs() < f32::EPSILON);
+        assert!((beats.flux([2.0, 1.0]) - 1.0 / 3.0).abs() < 1e-6);
+        assert!(beats.flux([1.0, 1.0]).abs() < f32::EPSILON);
+        // A different bin count has nothing to compare against.
+        assert!(beats.flux([5.0, 5.0, 5.0]).abs() < f32::EPSILON);
+    }
+
+    #[test]
+    fn periodicity_finds_the_pulse_tempo() {
+        let (tempo, clarity) = periodicity(&pulse(600, 50), WINDOW);
+        assert!((tempo - 120.0).abs() < 0.01);
+        assert!(clarity > 0.5);
+        let (tempo, _) = periodicity(&pulse(600, 60), WINDOW);
+        assert!((tempo - 100.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn periodicity_of_silence_has_no_clarity() {
+        assert!(periodicity(&[0.0; 600], WINDOW).1.abs() < f32::EPSILON);
+    }
+
+    #[test]
+    fn tracker_waits_for_history_then_follows_the_beat() {
+        let mut beats = BeatTracker::default();
+        let onsets = pulse(600, 50);
+        let first = onsets.iter().position(|&onset| beats.track(onset, WINDOW).is_some());
+        // Half of the history, rounded up to the next re-estimate.
+        assert_eq!(first, Some(299));
+
+        let mut beats = BeatTracker::default();
+        let beat = onsets.iter().filter_map(|&onset| beats.track(onset, WINDOW)).last().unwrap();
+        assert!((beat.period - 0.5).abs() < 1e-4);
+        // The newest window is 599, and the latest spike landed at 550.
+        assert!((beat.since - 0.49).abs() < 1e-4);
+        assert!(beat.strength > 0.5);
+    }
+
+    #[test]
+    fn analysis_estimates_once_after_enough_audible_windows() {
+        let mut analysis = Analysis::default();
+        analysis.listen(Some("track"));
+        for _ in 0..29 {
+            assert!(analysis.push(0.1, 0.2, 1.0).is_none());
+            // Silence doesn't count towards the listening time.
+            assert!(analysis.push(0.0, 0.2, 1.0).is_none());
+        }
+        let (uri, _) = analysis.push(0.1, 0.2, 1.0).unwrap();
+        assert_eq!(uri, "track");
+        assert!(analysis.push(0.1, 0.2, 1.0).is_none());
+    }
+
+    #[test]
+    fn analysis_pauses_without_a_track_and_restarts_on_a_new_one() {
+        let mut analysis = Analysis::default();
+        analysis.listen(Some("first"));
+        for _ in 0..20 {
+            analysis.push(0.1, 0.2, 1.0);
+        }
+        analysis.listen(None);
+        assert!(analysis.push(0.1, 0.2, 1.0).is_none());
+        assert_eq!(analysis.onsets.len(), 20);
+
+        analysis.listen(Some("first"));
+        assert_eq!(analysis.onsets.len(), 20);
+        analysis.listen(Some("second"));
+        assert!(analysis.onsets.is_empty());
+        assert_eq!(analysis.track.as_deref(), Some("second"));
+    }
+}
@@ -0,0 +1,63 @@
+use super::enrichment::AlbumArt;
+use crate::{
+    app::{cache::DiskCache, config::PaletteOptions},
+    render::track::PALETTE_COLORS,
+};
+use isthmus::{Unorm8x4, glam::Vec4};
+use std::{array, fs, path::PathBuf};
+use tracing::warn;
+
+const CACHE: DiskCache = DiskCache {
+    directory: "art",
+    max_bytes: 64 * 1024 * 1024,
+};
+const PALETTE_BYTES: usize = PALETTE_COLORS * 4;
+
+const fn entry_bytes(size: u32) -> usize {
+    PALETTE_BYTES + (size * size * 4) as usize
+}
+
+/// Entries are named by the image URL and palette options, if the art carries a palette, so they follow option
+/// changes, and by their size.
+fn path(url: &str, size: u32, options: Option<&PaletteOptions>) -> PathBuf {
+    let options = options.map(|options| {
+        let [min_lightness, max_lightness] = options.lightness;
+        [
+            f32::from(options.min_spread),
+            options.min_chroma,
+            min_lightness,
+            max_lightness,
+            f32::from(u8::from(options.harmony)),
+        ]
+    });
+    let key = url.bytes().chain(options.into_iter().flatten().flat_map(|value| value.to_bits().to_le_bytes()));
+    CACHE.path(key, &format!("-{size}.rgba"))
+}
+
+/// The cached art for `url`.
+pub(super) fn load(url: &str, size: u32, options: Option<&PaletteOptions>) -> Option<AlbumArt> {
+    let path = path(url, size, options);
+    let bytes = DiskCache::read(&path)?;
+    if bytes.len() != entry_bytes(size) {
+        warn!(?path, "Discarding malformed cached art");
+        let _ = fs::remove_file(&path);
+        return None;
+    }
+    let (palette, pixels) = bytes.split_at(PALETTE_BYTES);
+    Some(AlbumArt {
+        palette: array::from_fn(|index| Unorm8x4::from_vec4(Vec4::from_array(array::from_fn(|channel| f32::from(palette[index * 4 + channel]) / 255.0)))),
+        pixels: pixels.into(),
+    })
+}
+
+/// Saves resized art and its palette.
+pub(super) fn store(url: &str, size: u32, options: Option<&PaletteOptions>, art: &AlbumArt) {
+    let mut bytes = Vec::with_capacity(PALETTE_BYTES + art.pixels.len());
+    for color in art.palette {
+        bytes.extend(color.to_vec4().to_array().map(|channel| (channel * 255.0).round() as u8));
+    }
+    bytes.extend_from_slice(&art.pixels);
+    if let Err(error) = CACHE.store(&path(url, size, options), &bytes) {
+        warn!(%error, %url, "Failed to cache art");
+    }
+}
@@ -1,47 +1,69 @@
-use super::{MusicBackend, PlaybackState, Track, TrackId};
+use super::{
+    MusicBackend, PlaybackState, Track, TrackId, art_cache,
+    health::Health,
+    musicbrainz::{MusicBrainz, ReleaseQuery},
+    swatches::{Swatch, extract_palette},
+};
 use crate::{
-    app::{Background, CantusApp, update},
+    app::{
+        Background, CantusApp,
+        config::{Config, PaletteOptions, Services},
+        http::{HttpClient, Response},
+        update,
+    },
     render::{
-        lyrics::{self, LyricsRequest},
+        lyrics::{self, LyricsChain, LyricsRequest},
         text,
         track::{AudioFeatures, PALETTE_COLORS},
     },
 };
-use arrayvec::ArrayVec;
-use image::{RgbaImage, imageops};
-use isthmus::{Unorm8x4, glam::Vec3};
-use palette::{Clamp, IntoColor, Lch, color_theory::Analogous};
-use reqwest::Client;
+use image::imageops;
+use isthmus::Unorm8x4;
+use parking_lot::Mutex;
+use reqwest::Url;
 use serde::Deserialize;
 use std::{
-    array,
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     error::Error,
-    ops::Range,
+    fs,
+    sync::Arc,
     time::{Duration, Instant},
 };
 use tokio::task::spawn_blocking;
 use tracing::warn;
 
-const RECCO_FEATURES_URL: &str = "https://api.reccobeats.com/v1/audio-features";
-const RETRY_DELAY: Duration = Duration::from_secs(30);
 pub const IMAGE_SIZE: u32 = 64;
+/// Largest art fetched for pills drawn bigger than [`IMAGE_SIZE`], and the size of each large atlas layer.
+pub const MAX_LARGE_IMAGE_SIZE: u32 = 256;
+/// How long art that downloaded but couldn't be decoded waits before it is fetched again.
+const UNDECODABLE_ART_RETRY: Duration = Duration::from_secs(10 * 60);
 pub type ArtState = Fetch<AlbumArt>;
 #[derive(Clone)]
 pub struct Enrichment {
     pub(crate) background: Background,
-    pub(crate) http: Client,
+    pub(crate) http: HttpClient,
+    pub health: Arc<Health>,
+    lyrics: Arc<LyricsChain>,
+    /// ReccoBeats base URL, or `None` to rely on local estimates alone.
+    reccobeats_url: Option<String>,
+    /// Release metadata source, or `None` when disabled.
+    musicbrainz: Option<Arc<MusicBrainz>>,
+    palette: PaletteOptions,
+    /// Large art downloads under way, so tracks sharing a cover start just one.
+    large_art_fetches: Arc<Mutex<HashSet<String>>>,
 }
 
 impl Enrichment {
-    pub(crate) fn new(background: Background) -> Self {
+    pub(crate) fn new(background: Background, config: &Config) -> Self {
         Self {
             background,
-            http: Client::builder()
-                .user_agent(concat!("Cantus/", env!("CARGO_PKG_VERSION")))
-                .timeout(Duration::from_secs(15))
-                .build()
-                .expect("failed to construct HTTP client"),
+            health: Arc::default(),
+            lyrics: Arc::new(LyricsChain::new(config)),
+            reccobeats_url: Services::endpoint(&config.services.reccobeats),
+            musicbrainz: MusicBrainz::new(config).map(Arc::new),
+            palette: config.palette,
+            large_art_fetches: Arc::default(),
+            http: HttpClient::default(),
         }
     }
 
@@ -54,10 +76,10 @@ impl Enrichment {
             album: track.album.clone(),
             duration_ms: track.duration_ms,
         };
-        let http = self.http.clone();
+        let (http, chain, health) = (self.http.clone(), Arc::clone(&self.lyrics), Arc::clone(&self.health));
         self.background.spawn(async move {
             let uri = request.uri.clone();
-            let state = fetch_lyrics(&request, &http, &music, &shaper).await;
+            let state = fetch_lyrics(&request, &http, &music, &chain, &shaper, &health).await;
             Some(update(move |app| {
                 if let Some(track) = app
                     .playback
@@ -70,6 +92,26 @@ impl Enrichment {
             }))
         });
     }
+
+    /// Fetches `url` at `size` for the tracks waiting on it, unless it is already on its way.
+    /// Its palette is left out; pills take theirs from the small copy.
+    pub(crate) fn request_large_art(&self, url: &str, size: u32) {
+        if !self.large_art_fetches.lock().insert(url.to_owned()) {
+            return;
+        }
+        let (http, health, url) = (self.http.clone(), Arc::clone(&self.health), url.to_owned());
+        self.background.spawn(async move {
+            let state = fetch_art(&http, &health, &url, size, None).await;
+            Some(update(move |app| {
+                app.enrichment.large_art_fetches.lock().remove(&url);
+                for track in app.playback.tracks_mut() {
+                    if track.image.as_deref() == Some(url.as_str()) && matches!(track.runtime.large_art, Fetch::Fetching) {
+                        track.runtime.large_art = state.clone();
+                    }
+                }
+            }))
+        });
+    }
 }
 
 #[derive(Clone)]
@@ -86,8 +128,9 @@ impl<T> Default for Fetch<T> {
 }
 
 impl<T> Fetch<T> {
-    pub fn retry() -> Self {
-        Self::Missing(Instant::now() + RETRY_DELAY)
+    /// Records a failed fetch from `url`, to be retried once its endpoint's backoff passes.
+    pub fn retry(health: &Health, url: &str) -> Self {
+        Self::Missing(health.failed(url))
     }
 
     pub fn request(&mut self, now: Instant) -> bool {
@@ -109,7 +152,7 @@ impl<T> Fetch<T> {
 #[derive(Clone)]
 pub struct AlbumArt {
     pub pixels: Box<[u8]>,
-    palette: [Unorm8x4; PALETTE_COLORS],
+    pub(super) palette: [Unorm8x4; PALETTE_COLORS],
 }
 
 #[derive(Deserialize)]
@@ -131,58 +174,90 @@ impl Fetch<AlbumArt> {
     }
 }
 
-async fn fetch_lyrics(request: &LyricsRequest, http: &Client, music: &MusicBackend, shaper: &text::Shaper) -> Fetch<lyrics::Lyrics> {
-    let result = if let Some(lyrics) = lyrics::fetch(http, request).await {
-        Ok(lyrics)
-    } else if let Some(id) = request.track_id {
-        music.lyrics(id).await
-    } else {
-        Ok(Vec::new())
-    };
-    match result {
-        Ok(segments) => Fetch::Ready(lyrics::Lyrics::shape(segments, request.duration_ms as f32, shaper).unwrap_or_default()),
+async fn fetch_lyrics(request: &LyricsRequest, http: &HttpClient, music: &MusicBackend, chain: &LyricsChain, shaper: &text::Shaper, health: &Health) -> Fetch<lyrics::Lyrics> {
+    match chain.fetch(http, music, health, request).await {
+        Ok(found) => Fetch::Ready(
+            found
+                .and_then(|(_, segments)| lyrics::Lyrics::shape(segments, request.duration_ms as f32, chain.annotation, shaper))
+                .unwrap_or_default(),
+        ),
+        Err(retry_at) => Fetch::Missing(retry_at),
+    }
+}
+
+/// Art at `size` pixels square from the disk cache, or downloaded, resized and cached, with a palette when `options` are given.
+/// Local `file://` art, as video players report it, is read directly and never cached.
+/// Only failed downloads count against the art host; art that can't be decoded waits [`UNDECODABLE_ART_RETRY`].
+async fn fetch_art(http: &HttpClient, health: &Health, url: &str, size: u32, options: Option<PaletteOptions>) -> ArtState {
+    let local = Url::parse(url).ok().filter(|url| url.scheme() == "file").and_then(|url| url.to_file_path().ok());
+    if local.is_none() {
+        let cache_url = url.to_owned();
+        if let Ok(Some(art)) = spawn_blocking(move || art_cache::load(&cache_url, size, options.as_ref())).await {
+            return Fetch::Ready(art);
+        }
+    }
+    let loaded: Result<_, Box<dyn Error + Send + Sync>> = async {
+        Ok(match local.clone() {
+            Some(path) => spawn_blocking(move || fs::read(path)).await??,
+            None => http.get(url).send().await?.error_for_status()?.bytes(),
+        })
+    }
+    .await;
+    let bytes = match loaded {
+        Ok(bytes) => bytes,
         Err(error) => {
-            warn!(%error, track = request.name, "Failed to fetch lyrics");
-            Fetch::retry()
+            warn!(%error, %url, "Failed to load image");
+            // A missing local file says nothing about the art servers.
+            return if local.is_some() { Fetch::default() } else { Fetch::retry(health, url) };
         }
+    };
+    if local.is_none() {
+        health.succeeded(url);
     }
-}
 
-async fn fetch_art(http: &Client, url: &str) -> ArtState {
-    let result: Result<_, Box<dyn Error + Send + Sync>> = async {
-        let bytes = http.get(url).send().await?.error_for_status()?.bytes().await?;
+    let cache_url = local.is_none().then(|| url.to_owned());
+    let decoded: Result<_, Box<dyn Error + Send + Sync>> = async {
         Ok(spawn_blocking(move || {
-            let image = image::load_from_memory(&bytes)?
-                .resize_to_fill(IMAGE_SIZE, IMAGE_SIZE, imageops::FilterType::Lanczos3)
-                .to_rgba8();
-            Ok::<_, image::ImageError>(AlbumArt {
-                palette: image_palette(&image),
-                pixels: image.into_raw().into_boxed_slice(),
-            })
+            let image = image::load_from_memory(&bytes)?;
+            let resized = image.resize_to_fill(size, size, imageops::FilterType::Lanczos3).to_rgba8();
+            let palette = options
+                .as_ref()
+                .and_then(|options| extract_palette(&resized, options))
+                .map_or_else(|| [Unorm8x4::default(); PALETTE_COLORS], |swatches| swatches.map(Swatch::to_unorm));
+            let art = AlbumArt {
+                palette,
+                pixels: resized.into_raw().into_boxed_slice(),
+            };
+            if let Some(url) = cache_url {
+                art_cache::store(&url, size, options.as_ref(), &art);
+            }
+            Ok::<_, image::ImageError>(art)
         })
         .await??)
     }
     .await;
-    match result {
+    match decoded {
         Ok(art) => Fetch::Ready(art),
         Err(error) => {
-            warn!(%error, %url, "Failed to load image");
-            Fetch::retry()
+            warn!(%error, %url, "Failed to decode image");
+            Fetch::Missing(Instant::now() + UNDECODABLE_ART_RETRY)
         }
     }
 }
 
 fn art_slots(playback: &mut PlaybackState) -> impl Iterator<Item = (&str, &mut ArtState)> {
-    playback
-        .queue
-        .iter_mut()
-        .filter_map(|track| track.image.as_deref().map(|url| (url, &mut track.runtime.art)))
-        .chain(
-            playback
-                .playlists
-                .iter_mut()
-                .filter_map(|playlist| playlist.image_url.as_deref().map(|url| (url, &mut playlist.art))),
-        )
+    let (tracks, playlists) = (
+        playback
+            .queue
+            .iter_mut()
+            .chain(playback.interruption.iter_mut().map(|interruption| &mut interruption.track)),
+        &mut playback.playlists,
+    );
+    tracks.filter_map(|track| track.image.as_deref().map(|url| (url, &mut track.runtime.art))).chain(
+        playlists
+            .iter_mut()
+            .filter_map(|playlist| playlist.image_url.as_deref().map(|url| (url, &mut playlist.art))),
+    )
 }
 
 impl CantusApp {
@@ -196,7 +271,16 @@ impl CantusApp {
 
     fn refresh_enrichment(&mut self, include_audio: bool) {
         let now = Instant::now();
-        let mut audio = if include_audio {
+        let remote = self.enrichment.reccobeats_url.is_some();
+        if include_audio {
+            for track in self.playback.queue.iter_mut().filter(|track| track.id.is_none() || !remote) {
+                if let (Fetch::Missing(_), Some(estimate)) = (&track.runtime.audio_features, self.audio.estimate(&track.uri)) {
+                    track.runtime.audio_features = Fetch::Ready(estimate);
+                }
+            }
+        }
+        let health = Arc::clone(&self.enrichment.health);
+        let mut audio = if include_audio && self.enrichment.reccobeats_url.as_deref().is_some_and(|url| health.available(url, now)) {
             self.playback
                 .queue
                 .iter_mut()
@@ -208,35 +292,105 @@ impl CantusApp {
         audio.sort_unstable();
         audio.dedup();
 
-        if !audio.is_empty() {
-            let http = self.enrichment.http.clone();
+        if !audio.is_empty()
+            && let Some(base) = &self.enrichment.reccobeats_url
+        {
+            let (http, health, url) = (self.enrichment.http.clone(), Arc::clone(&health), format!("{base}/audio-features"));
             self.enrichment.background.spawn(async move {
-                let features = resolve_audio_features(&http, &audio).await;
+                let features = resolve_audio_features(&http, &health, &url, &audio).await;
                 Some(update(move |app| {
                     for track in &mut app.playback.queue {
                         let Some(features) = track.id.and_then(|id| features.get(&id)) else {
                             continue;
                         };
-                        track.runtime.audio_features = features.map_or_else(Fetch::default, Fetch::Ready);
+                        track.runtime.audio_features = match (features, app.audio.estimate(&track.uri)) {
+                            (Fetch::Ready(_), _) | (_, None) => features.clone(),
+                            (_, Some(estimate)) => Fetch::Ready(estimate),
+                        };
                     }
                 }))
             });
         }
 
+        if include_audio {
+            self.request_release_info(now);
+        }
+
         let mut art = art_slots(&mut self.playback)
-            .filter_map(|(url, state)| state.request(now).then(|| url.to_owned()))
+            .filter_map(|(url, state)| (health.available(url, now) && state.request(now)).then(|| url.to_owned()))
             .collect::<Vec<_>>();
         art.sort_unstable();
         art.dedup();
         for url in art {
-            let http = self.enrichment.http.clone();
+            let (http, health, palette) = (self.enrichment.http.clone(), Arc::clone(&health), self.enrichment.palette);
             self.enrichment.background.spawn(async move {
-                let state = fetch_art(&http, &url).await;
+                let state = fetch_art(&http, &health, &url, IMAGE_SIZE, Some(palette)).await;
                 Some(update(move |app| app.set_art_state(&url, &state)))
             });
         }
     }
 
+    /// Looks up release metadata for queued tracks, upcoming ones first since requests queue for the rate limit.
+    fn request_release_info(&mut self, now: Instant) {
+        let health = &self.enrichment.health;
+        let Some(musicbrainz) = self.enrichment.musicbrainz.as_ref() else {
+            return;
+        };
+        let index = self.playback.timeline.index.min(self.playback.queue.len());
+        let (history, upcoming) = self.playback.queue.split_at_mut(index);
+        let mut queries = Vec::<ReleaseQuery>::new();
+        for track in upcoming.iter_mut().chain(history.iter_mut().rev()) {
+            // Repeats of a queued track share one lookup.
+            if track.runtime.release.request(now) && !queries.iter().any(|query| query.uri == track.uri) {
+                queries.push(ReleaseQuery {
+                    uri: track.uri.clone(),
+                    isrc: track.isrc.clone(),
+                    name: track.name.clone(),
+                    artist: track.artist.clone(),
+                    duration_ms: track.duration_ms,
+                });
+            }
+        }
+        for query in queries {
+            let (http, health, musicbrainz) = (self.enrichment.http.clone(), Arc::clone(health), Arc::clone(musicbrainz));
+            self.enrichment.background.spawn(async move {
+                // Cached tracks load even while the source is down; the rest wait out its backoff.
+                let state = if let Some(release) = MusicBrainz::cached(&query.uri).await {
+                    Fetch::Ready(release)
+                } else if let Some(retry_at) = health.paused_until(&musicbrainz.url, Instant::now()) {
+                    Fetch::Missing(retry_at)
+                } else {
+                    match musicbrainz.release(&http, &query).await {
+                        Ok(release) => {
+                            health.succeeded(&musicbrainz.url);
+                            Fetch::Ready(release)
+                        }
+                        Err(error) => {
+                            warn!(%error, track = query.name, "Failed to fetch release info");
+                            Fetch::retry(&health, &musicbrainz.url)
+                        }
+                    }
+                };
+                Some(update(move |app| {
+                    for track in &mut app.playback.queue {
+                        if track.uri == query.uri && matches!(track.runtime.release, Fetch::Fetching) {
+                            track.runtime.release = state.clone();
+                        }
+                    }
+                }))
+            });
+        }
+    }
+
+    /// Uses a locally heard estimate for tracks the remote lookup has no features for.
+    pub(crate) fn apply_audio_estimate(&mut self, uri: &str, features: AudioFeatures) {
+        for track in self.playback.queue.iter_mut().filter(|track| track.uri == uri) {
+            if matches!(track.runtime.audio_features, Fetch::Missing(_)) {
+                track.runtime.audio_features = Fetch::Ready(features);
+            }
+        }
+    }
+
     fn set_art_state(&mut self, url: &str, state: &ArtState) {
         for (slot_url, slot) in art_slots(&mut self.playback) {
             if slot_url == url {
@@ -246,146 +400,41 @@ impl CantusApp {
     }
 }
 
-async fn resolve_audio_features(http: &Client, track_ids: &[TrackId]) -> HashMap<TrackId, Option<AudioFeatures>> {
-    let mut output = track_ids.iter().map(|&id| (id, None)).collect::<HashMap<_, _>>();
+/// Features for each of `track_ids`; tracks ReccoBeats doesn't know are retried on the next refresh,
+/// while a failed batch waits out the source's backoff.
+async fn resolve_audio_features(http: &HttpClient, health: &Health, url: &str, track_ids: &[TrackId]) -> HashMap<TrackId, Fetch<AudioFeatures>> {
+    let mut output = track_ids.iter().map(|&id| (id, Fetch::default())).collect::<HashMap<_, _>>();
     for batch in track_ids.chunks(40) {
+        let failed = |output: &mut HashMap<_, _>| {
+            let retry_at = health.failed(url);
+            output.extend(batch.iter().map(|&id| (id, Fetch::Missing(retry_at))));
+        };
         let ids = batch.iter().map(TrackId::as_str).collect::<Vec<_>>().join(",");
         let Ok(features) = http
-            .get(RECCO_FEATURES_URL)
+            .get(url)
             .query(&[("ids", &ids)])
             .send()
             .await
-            .and_then(reqwest::Response::error_for_status)
+            .and_then(Response::error_for_status)
             .inspect_err(|err| warn!("Failed to fetch ReccoBeats audio features: {err}"))
         else {
+            failed(&mut output);
             continue;
         };
         let Ok(features) = features
             .json::<ReccoResponse>()
-            .await
             .inspect_err(|err| warn!("Failed to decode ReccoBeats audio features: {err}"))
         else {
+            failed(&mut output);
             continue;
         };
+        health.succeeded(url);
         output.extend(
             features
                 .items
                 .into_iter()
-                .filter_map(|item| Some((item.href.rsplit('/').next()?.parse().ok()?, Some(item.features.normalized())))),
+                .filter_map(|item| Some((item.href.rsplit('/').next()?.parse().ok()?, Fetch::Ready(item.features.normalized())))),
         );
     }
     output
 }
-
-fn complete_palette(colors: &mut ArrayVec<(Lch, f32), PALETTE_COLORS>) {
-    colors.sort_by(|a, b| b.1.total_cmp(&a.1));
-    let mut index = 1;
-    while index < colors.len() {
-        let (color, weight) = colors[index];
-        if let Some(duplicate) = colors[..index].iter().position(|(other, _)| (color.hue - other.hue).into_degrees().abs() < 20.0) {
-            colors[duplicate].1 += weight;
-            colors.remove(index);
-        } else {
-            index += 1;
-        }
-    }
-
-    let measured = colors.len();
-    for index in 0..PALETTE_COLORS - measured {
-        let (source, weight) = colors[index % measured];
-        let (lower, upper) = source.analogous();
-        let mut generated = match index {
-            2 if measured == 1 => source.analogous_secondary().0,
-            index if index % 2 == 0 => lower,
-            _ => upper,
-        };
-        generated.chroma = generated.chroma.max(35.0);
-        colors.push((generated, weight * 0.5));
-    }
-    colors.sort_by(|a, b| a.0.l.total_cmp(&b.0.l));
-}
-
-fn palette_color((color, weight): (Lch, f32), total: f32) -> Unorm8x4 {
-    let rgb: palette::Srgb = color.into_color();
-    let rgb = rgb.clamp();
-    Unorm8x4::from_vec4(Vec3::new(rgb.red, rgb.green, rgb.blue).extend((weight / total).max(1.0 / 255.0)))
-}
-
-const fn component(color: &palette::Lab, channel: usize) -> f32 {
-    [color.l, color.a, color.b][channel]
-}
-
-fn dominant_colors(pixels: &mut [palette::Lab]) -> ArrayVec<(Lch, f32), PALETTE_COLORS> {
-    let mut buckets = ArrayVec::<Range<usize>, PALETTE_COLORS>::new();
-    buckets.push(0..pixels.len());
-
-    while buckets.len() < PALETTE_COLORS {
-        let Some((bucket_index, channel)) = buckets
-            .iter()
-            .enumerate()
-            .filter(|(_, range)| range.len() > 1)
-            .map(|(index, range)| {
-                let mut min = [f32::INFINITY; 3];
-                let mut max = [f32::NEG_INFINITY; 3];
-                for color in &pixels[range.clone()] {
-                    for channel in 0..3 {
-                        min[channel] = min[channel].min(component(color, channel));
-                        max[channel] = max[channel].max(component(color, channel));
-                    }
-                }
-                let (channel, spread) = (0..3).map(|channel| (channel, max[channel] - min[channel])).max_by(|a, b| a.1.total_cmp(&b.1)).unwrap();
-                (index, channel, spread * range.len() as f32)
-            })
-            .max_by(|a, b| a.2.total_cmp(&b.2))
-            .map(|(index, channel, _)| (index, channel))
-        else {
-            break;
-        };
-
-        let range = buckets.swap_remove(bucket_index);
-        pixels[range.clone()].sort_unstable_by(|a, b| component(a, channel).total_cmp(&component(b, channel)));
-        let middle = range.start + range.len() / 2;
-        buckets.push(range.start..middle);
-        buckets.push(middle..range.end);
-    }
-
-    buckets
-        .into_iter()
-        .map(|range| {
-            let weight = range.len() as f32;
-            let sum = pixels[range].iter().fold([0.0; 3], |mut sum, color| {
-                sum[0] += color.l;
-                sum[1] += color.a;
-                sum[2] += color.b;
-                sum
-            });
-            (palette::Lab::new(sum[0] / weight, sum[1] / weight, sum[2] / weight).into_color(), weight)
-        })
-        .collect()
-}
-
-fn image_palette(image: &RgbaImage) -> [Unorm8x4; PALETTE_COLORS] {
-    let srgb_to_lab = |pixel: &image::Rgba<u8>| palette::Srgb::new(f32::from(pixel[0]) / 255.0, f32::from(pixel[1]) / 255.0, f32::from(pixel[2]) / 255.0).into_color();
-    let mut pixels: Vec<palette::Lab> = image
-        .pixels()
-        .filter(|pixel| {
-            let max = pixel[0].max(pixel[1]).max(pixel[2]);
-            let min = pixel[0].min(pixel[1]).min(pixel[2]);
-            pixel[3] >= 128 && max - min > 30
-        })
-        .map(srgb_to_lab)
-        .collect();
-    let use_harmony = !pixels.is_empty();
-    if !use_harmony {
-        pixels.extend(image.pixels().filter(|pixel| pixel[3] >= 128).map(srgb_to_lab));
-    }
-    if pixels.is_empty() {
-        return [Unorm8x4::default(); PALETTE_COLORS];
-    }
-    let mut colors = dominant_colors(&mut pixels);
-    if use_harmony {
-        complete_palette(&mut colors);
-    }
-    let total = colors.iter().map(|(_, weight)| weight).sum();
-    array::from_fn(|index| palette_color(colors[index % colors.len()], total))
-}
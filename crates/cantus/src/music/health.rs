@@ -0,0 +1,93 @@
+use parking_lot::Mutex;
+use std::{
+    collections::HashMap,
+    fmt::Write,
+    time::{Duration, Instant},
+};
+use tracing::{info, warn};
+
+/// Delay after the first failure, doubled for each consecutive one.
+const BASE_DELAY: Duration = Duration::from_secs(30);
+const MAX_DELAY: Duration = Duration::from_secs(30 * 60);
+/// Consecutive failures after which an endpoint counts as down and every request to it waits.
+const DOWN_AFTER: u32 = 3;
+
+#[derive(Default, Clone, Copy)]
+struct EndpointHealth {
+    failures: u32,
+    retry_at: Option<Instant>,
+}
+
+impl EndpointHealth {
+    const fn down(self) -> bool {
+        self.failures >= DOWN_AFTER
+    }
+}
+
+/// Consecutive failures of each remote service enrichment depends on, shared by every request to it.
+///
+/// Services are told apart by the host of the URL given, so each image host and mirror backs off on its own.
+#[derive(Default)]
+pub struct Health {
+    endpoints: Mutex<HashMap<String, EndpointHealth>>,
+}
+
+impl Health {
+    /// The host, and port if any, that `url` goes to; a bare name such as `spotify` is its own endpoint.
+    pub fn endpoint(url: &str) -> &str {
+        let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+        rest.split(['/', '?', '#']).next().unwrap_or(rest)
+    }
+
+    /// When a request to `url` may next start, or `None` if it may start now; a down endpoint waits out its backoff.
+    pub fn paused_until(&self, url: &str, now: Instant) -> Option<Instant> {
+        let state = self.endpoints.lock().get(Self::endpoint(url)).copied()?;
+        state.retry_at.filter(|&retry_at| state.down() && retry_at > now)
+    }
+
+    pub fn available(&self, url: &str, now: Instant) -> bool {
+        self.paused_until(url, now).is_none()
+    }
+
+    /// Records a failed request, returning when the endpoint of `url` should next be tried.
+    /// The delay doubles per consecutive failure, jittered so retries don't land together.
+    pub fn failed(&self, url: &str) -> Instant {
+        let endpoint = Self::endpoint(url);
+        let mut endpoints = self.endpoints.lock();
+        let state = endpoints.entry(endpoint.to_owned()).or_default();
+        state.failures = state.failures.saturating_add(1);
+        let backoff = BASE_DELAY.saturating_mul(1 << (state.failures - 1).min(16)).min(MAX_DELAY);
+        let delay = backoff.mul_f32(fastrand::f32().mul_add(0.5, 0.75));
+        let retry_at = Instant::now() + delay;
+        state.retry_at = Some(retry_at);
+        if state.failures == DOWN_AFTER {
+            warn!(endpoint, retry_in = ?delay, "Enrichment source unavailable; pausing its requests");
+        }
+        retry_at
+    }
+
+    pub fn succeeded(&self, url: &str) {
+        let endpoint = Self::endpoint(url);
+        if let Some(state) = self.endpoints.lock().remove(endpoint)
+            && state.down()
+        {
+            info!(endpoint, "Enrichment source recovered");
+        }
+    }
+
+    /// A short description of the endpoints currently down, such as `lrclib.net, musicbrainz.org unavailable · retry in 40s`.
+    pub fn summary(&self, now: Instant) -> Option<String> {
+        let endpoints = self.endpoints.lock();
+        let mut down = endpoints.iter().filter(|(_, state)| state.down()).collect::<Vec<_>>();
+        let retry_at = down.iter().filter_map(|(_, state)| state.retry_at).min()?;
+        down.sort_unstable_by_key(|&(endpoint, _)| endpoint);
+        let mut summary = down.iter().map(|(endpoint, _)| endpoint.as_str()).collect::<Vec<_>>().join(", ");
+        let seconds = retry_at.saturating_duration_since(now).as_secs();
+        if seconds >= 60 {
+            let _ = write!(summary, " unavailable · retry in {}m", seconds.div_ceil(60));
+        } else {
+            let _ = write!(summary, " unavailable · retry in {seconds}s");
+        }
+        Some(summary)
+    }
+}
@@ -1,20 +1,35 @@
 use crate::{
-    app::{AppUpdater, Background, config::Config},
+    app::{
+        AppUpdater, Background,
+        config::Config,
+        platform::{Current as Platform, Platform as _},
+    },
     render::{lyrics::Lyrics, track::AudioFeatures},
 };
 use arrayvec::ArrayString;
 use std::{collections::HashSet, error::Error, mem, sync::Arc, time::Instant};
 
+mod analysis;
+mod art_cache;
 mod enrichment;
+mod health;
+mod musicbrainz;
 mod spotify;
+mod swatches;
 
 pub(crate) use crate::render::lyrics::LyricSegment;
-pub use enrichment::{AlbumArt, ArtState, Enrichment, Fetch, IMAGE_SIZE};
+pub use analysis::AudioTap;
+pub use enrichment::{AlbumArt, ArtState, Enrichment, Fetch, IMAGE_SIZE, MAX_LARGE_IMAGE_SIZE};
+pub use health::Health;
+pub use musicbrainz::ReleaseInfo;
+pub use swatches::{Swatch, extract_palette};
 
 pub type TrackId = ArrayString<22>;
 pub type PlaylistId = ArrayString<22>;
 pub type MusicResult<T> = Result<T, Box<dyn Error + Send + Sync>>;
 pub const TRACK_SPACING_MS: f32 = 4000.0;
+/// Span given to the unplayed part of media that reports no length, such as a live stream.
+const OPEN_ENDED_MS: f32 = 60_000.0;
 pub(super) type PlaylistTracks = Arc<HashSet<TrackId>>;
 
 #[derive(Default)]
@@ -23,6 +38,30 @@ pub struct PlaybackState {
     pub queue: Vec<Track>,
     pub playlists: Vec<CondensedPlaylist>,
     pub timeline: Timeline,
+    pub mixer: Mixer,
+    /// Media from another player, shown at the playhead with the queue pushed aside.
+    pub interruption: Option<Interruption>,
+}
+
+/// PipeWire volumes, each signed: magnitude is the level, negative means muted.
+#[derive(Default)]
+pub struct Mixer {
+    /// The default audio sink.
+    pub sink: f32,
+    /// The music player's own playback stream, while it has one.
+    pub player: Option<PlayerStream>,
+}
+
+#[derive(Clone, Copy)]
+pub struct PlayerStream {
+    /// PipeWire node id, as `wpctl` addresses it.
+    pub node: u32,
+    pub volume: f32,
+}
+
+/// Steps a signed volume by scroll notches, keeping its mute sign.
+pub fn step_volume(volume: f32, notches: i32) -> f32 {
+    (volume.abs() - notches as f32 * 0.05).clamp(0.0, 1.0).copysign(volume)
 }
 
 /// The observed and visually smoothed position of the playback queue.
@@ -59,7 +98,109 @@ impl Timeline {
     }
 }
 
+/// What a player other than the music service reports over MPRIS.
+pub struct Media {
+    /// The player's bus name, which its controls are sent to.
+    pub player: String,
+    pub title: String,
+    pub artist: String,
+    pub art_url: Option<String>,
+    /// Zero when the player reports no length.
+    pub length_ms: u32,
+    pub position_ms: f32,
+    pub rate: f32,
+    pub playing: bool,
+}
+
+/// Another player's media, drawn as a pill at the playhead that the queue makes room for.
+pub struct Interruption {
+    pub player: String,
+    /// The media as a queue-less track, so it draws and fetches art like one.
+    pub track: Track,
+    pub playing: bool,
+    position_ms: f32,
+    rate: f32,
+    observed_at: Instant,
+    /// Eases toward 1 while open and back to 0 once the media goes away.
+    pub presence: f32,
+    pub open: bool,
+}
+
+impl Interruption {
+    /// Milliseconds played and left to play, extrapolated from the player's last report.
+    pub fn extent_ms(&self) -> (f32, f32) {
+        let position = self.position_ms + self.observed_at.elapsed().as_millis() as f32 * self.rate;
+        let duration = self.track.duration_ms as f32;
+        if duration > 0.0 {
+            (position.min(duration), (duration - position).max(0.0))
+        } else {
+            (position, OPEN_ENDED_MS)
+        }
+    }
+
+    /// Asks the player to play or pause, showing the new state until it reports back.
+    pub fn toggle(&mut self, background: &Background) {
+        Platform::toggle_media(background, &self.player);
+        self.position_ms = self.extent_ms().0;
+        self.observed_at = Instant::now();
+        self.playing = !self.playing;
+        self.rate = f32::from(self.playing);
+    }
+}
+
 impl PlaybackState {
+    /// Queued tracks, followed by the interruption's media.
+    pub fn tracks_mut(&mut self) -> impl Iterator<Item = &mut Track> {
+        self.queue.iter_mut().chain(self.interruption.iter_mut().map(|interruption| &mut interruption.track))
+    }
+
+    /// Follows another player's media, returning whether it changed and needs art.
+    /// Paused media stays only while the music service is paused too; without media the interruption closes.
+    pub fn set_interruption(&mut self, media: Option<Media>, observed_at: Instant) -> bool {
+        let Some(media) = media.filter(|media| media.playing || !self.playing) else {
+            if let Some(interruption) = &mut self.interruption {
+                interruption.open = false;
+            }
+            return false;
+        };
+        let current = self
+            .interruption
+            .as_mut()
+            .filter(|interruption| interruption.player == media.player && interruption.track.name == media.title && interruption.track.image == media.art_url);
+        if let Some(interruption) = current {
+            interruption.track.artist = media.artist;
+            interruption.track.duration_ms = media.length_ms;
+            interruption.playing = media.playing;
+            interruption.position_ms = media.position_ms;
+            interruption.rate = media.rate;
+            interruption.observed_at = observed_at;
+            interruption.open = true;
+            return false;
+        }
+        let track = Track {
+            id: None,
+            isrc: None,
+            uri: media.player.clone(),
+            name: media.title,
+            artist: media.artist,
+            album: String::new(),
+            image: media.art_url,
+            duration_ms: media.length_ms,
+            runtime: TrackRuntime::default(),
+        };
+        self.interruption = Some(Interruption {
+            player: media.player,
+            track,
+            playing: media.playing,
+            position_ms: media.position_ms,
+            rate: media.rate,
+            observed_at,
+            presence: self.interruption.as_ref().map_or(0.0, |interruption| interruption.presence),
+            open: true,
+        });
+        true
+    }
+
     fn observe(&mut self, index: usize, position_ms: f32, rate: f32, observed_at: Instant) {
         self.timeline.index = index.min(self.queue.len().saturating_sub(1));
         self.timeline.position_ms = position_ms;
@@ -121,6 +262,8 @@ impl PlaybackState {
 
 pub struct Track {
     pub id: Option<TrackId>,
+    /// International Standard Recording Code, when the music service reports one.
+    pub isrc: Option<String>,
     pub uri: String,
     pub name: String,
     pub artist: String,
@@ -134,10 +277,14 @@ pub struct Track {
 pub struct TrackRuntime {
     /// Album art, shared with other slots on the same URL and freed with the track.
     pub art: ArtState,
+    /// Album art at the size a hovered or expanded pill draws it, when that is bigger than the small copy.
+    pub large_art: ArtState,
     pub playlist_expansion: f32,
     pub detail_alpha: f32,
     pub primary_icon_alpha: f32,
     pub audio_features: Fetch<AudioFeatures>,
+    /// Genres, release year and label from MusicBrainz.
+    pub release: Fetch<ReleaseInfo>,
     pub(crate) lyrics: Fetch<Lyrics>,
 }
 
@@ -0,0 +1,193 @@
+use crate::app::{
+    cache::TrackCache,
+    config::{Config, Services},
+    http::{Error, HttpClient},
+};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use std::{
+    cmp::Reverse,
+    time::{Duration, Instant},
+};
+use tokio::{sync::Mutex, time};
+
+/// Search score, out of 100, below which a recording isn't trusted to be the track.
+const MIN_SCORE: u8 = 90;
+/// How far a recording's length may differ from the track's.
+const LENGTH_TOLERANCE_MS: u32 = 5000;
+const MAX_GENRES: usize = 3;
+/// Slowest rate requests are spaced to, so a tiny configured rate can't overflow the interval.
+const MIN_REQUESTS_PER_SECOND: f32 = 0.001;
+const CACHE: TrackCache = TrackCache {
+    directory: "releases",
+    found_expiry: Duration::from_secs(30 * 24 * 60 * 60),
+    missing_expiry: Duration::from_secs(3 * 24 * 60 * 60),
+};
+
+/// Genres, release year and label of a track's recording.
+#[derive(Clone, Default, Debug, Deserialize, Serialize)]
+pub struct ReleaseInfo {
+    /// Most voted first.
+    pub genres: Vec<String>,
+    pub year: Option<u16>,
+    pub label: Option<String>,
+}
+
+/// What a track is looked up by; the ISRC when known, otherwise its name, artist and length.
+pub(super) struct ReleaseQuery {
+    pub uri: String,
+    pub isrc: Option<String>,
+    pub name: String,
+    pub artist: String,
+    pub duration_ms: u32,
+}
+
+/// A MusicBrainz-compatible API, with requests spaced to its rate limit.
+pub(super) struct MusicBrainz {
+    pub(super) url: String,
+    interval: Duration,
+    next_request: Mutex<Instant>,
+}
+
+#[derive(Deserialize)]
+struct RecordingSearch {
+    #[serde(default)]
+    recordings: Vec<RecordingMatch>,
+}
+
+#[derive(Deserialize)]
+struct RecordingMatch {
+    id: String,
+    #[serde(default)]
+    score: u8,
+    length: Option<u32>,
+}
+
+#[derive(Deserialize)]
+struct Recording {
+    #[serde(default)]
+    genres: Vec<Genre>,
+    #[serde(rename = "first-release-date", default)]
+    first_release_date: String,
+    #[serde(default)]
+    releases: Vec<Release>,
+}
+
+#[derive(Deserialize)]
+struct Release {
+    id: String,
+    #[serde(default)]
+    date: String,
+    status: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ReleaseDetails {
+    #[serde(default)]
+    genres: Vec<Genre>,
+    #[serde(rename = "label-info", default)]
+    label_info: Vec<LabelInfo>,
+}
+
+#[derive(Deserialize)]
+struct Genre {
+    name: String,
+    #[serde(default)]
+    count: u32,
+}
+
+#[derive(Deserialize)]
+struct LabelInfo {
+    label: Option<Label>,
+}
+
+#[derive(Deserialize)]
+struct Label {
+    name: String,
+}
+
+impl MusicBrainz {
+    /// The configured API, or `None` when it is disabled.
+    pub(super) fn new(config: &Config) -> Option<Self> {
+        let rate = config.services.musicbrainz_requests_per_second;
+        Some(Self {
+            url: Services::endpoint(&config.services.musicbrainz).filter(|_| rate > 0.0)?,
+            interval: Duration::from_secs_f32(1.0 / rate.max(MIN_REQUESTS_PER_SECOND)),
+            next_request: Mutex::new(Instant::now()),
+        })
+    }
+
+    /// Release info looked up for the track before, unless it has expired.
+    pub(super) async fn cached(uri: &str) -> Option<ReleaseInfo> {
+        CACHE.read::<ReleaseInfo>(uri).await.map(Option::unwrap_or_default)
+    }
+
+    /// Resolves the track's recording and its earliest official release, caching the answer on disk
+    /// per track; a track without a match has empty info.
+    ///
+    /// # Errors
+    /// Returns an error when a request fails or its response can't be decoded.
+    pub(super) async fn release(&self, http: &HttpClient, query: &ReleaseQuery) -> Result<ReleaseInfo, Error> {
+        let release = self.lookup(http, query).await?;
+        CACHE.store(&query.uri, release.as_ref());
+        Ok(release.unwrap_or_default())
+    }
+
+    async fn lookup(&self, http: &HttpClient, query: &ReleaseQuery) -> Result<Option<ReleaseInfo>, Error> {
+        let search = match &query.isrc {
+            Some(isrc) => format!("isrc:{isrc}"),
+            None => format!("recording:\"{}\" AND artist:\"{}\"", escape(&query.name), escape(&query.artist)),
+        };
+        let matches = self.get::<RecordingSearch>(http, "recording", &[("query", &search), ("limit", "5")]).await?;
+        let Some(found) = matches.recordings.into_iter().find(|recording| {
+            let length_matches = recording.length.is_none_or(|length| length.abs_diff(query.duration_ms) <= LENGTH_TOLERANCE_MS);
+            recording.score >= MIN_SCORE && (query.isrc.is_some() || length_matches)
+        }) else {
+            return Ok(None);
+        };
+
+        let recording = self.get::<Recording>(http, &format!("recording/{}", found.id), &[("inc", "genres+releases")]).await?;
+        let release = recording
+            .releases
+            .iter()
+            .filter(|release| !release.date.is_empty())
+            .min_by_key(|release| (release.status.as_deref() != Some("Official"), release.date.as_str()))
+            .or_else(|| recording.releases.first());
+        let (release_genres, labels) = match release {
+            Some(release) => {
+                let details = self.get::<ReleaseDetails>(http, &format!("release/{}", release.id), &[("inc", "labels+genres")]).await?;
+                (details.genres, details.label_info)
+            }
+            None => Default::default(),
+        };
+
+        // Recordings are tagged less often than releases.
+        let mut genres = if recording.genres.is_empty() { release_genres } else { recording.genres };
+        genres.sort_by_key(|genre| Reverse(genre.count));
+        let dates = [recording.first_release_date.as_str(), release.map_or("", |release| release.date.as_str())];
+        Ok(Some(ReleaseInfo {
+            genres: genres.into_iter().take(MAX_GENRES).map(|genre| genre.name).collect(),
+            year: dates.iter().find_map(|date| date.get(..4)?.parse().ok()),
+            label: labels.into_iter().filter_map(|info| info.label).map(|label| label.name).find(|name| name != "[no label]"),
+        }))
+    }
+
+    async fn get<T: DeserializeOwned>(&self, http: &HttpClient, path: &str, query: &[(&str, &str)]) -> Result<T, Error> {
+        {
+            let mut next_request = self.next_request.lock().await;
+            time::sleep_until((*next_request).into()).await;
+            *next_request = Instant::now() + self.interval;
+        }
+        http.get(format!("{}/{path}", self.url))
+            .query(query)
+            .query(&[("fmt", "json")])
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+    }
+}
+
+/// `text` quoted safely inside a Lucene phrase.
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
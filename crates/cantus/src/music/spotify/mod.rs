@@ -155,6 +155,7 @@ impl SpotifyBackend {
                     text: line.words.clone(),
                     lane: 0,
                     line_end: true,
+                    annotation: None,
                 })
             })
             .collect())
@@ -546,6 +547,7 @@ struct TrackDetails {
     album: String,
     image: Option<String>,
     duration_ms: u32,
+    isrc: Option<String>,
 }
 
 impl TrackDetails {
@@ -556,6 +558,7 @@ impl TrackDetails {
             album: track.album.get_or_default().name().to_owned(),
             image: track_image_url(track),
             duration_ms: u32::try_from(track.duration()).unwrap_or_default(),
+            isrc: track.external_id.iter().find(|id| id.type_().eq_ignore_ascii_case("isrc")).map(|id| id.id().to_owned()),
         }
     }
 }
@@ -572,6 +575,7 @@ fn track_from_provided(track: &ProvidedTrack, track_metadata: Option<&TrackDetai
     };
     Track {
         id: track.uri.strip_prefix("spotify:track:").and_then(|id| id.parse().ok()),
+        isrc: track_metadata.and_then(|details| details.isrc.clone()),
         uri: track.uri.clone(),
         name: text("title", |details| &details.name),
         artist: text("artist_name", |details| &details.artist),
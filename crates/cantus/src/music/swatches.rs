@@ -0,0 +1,277 @@
+use crate::{app::config::PaletteOptions, render::track::PALETTE_COLORS};
+use arrayvec::ArrayVec;
+use image::{Rgba, RgbaImage};
+use isthmus::{Unorm8x4, glam::Vec3};
+use palette::{Clamp, IntoColor, Lab, Lch, Srgb, color_theory::Analogous};
+use std::{array, ops::Range};
+
+/// Measured hues closer than this merge into one swatch before harmony colours fill the palette.
+const HUE_MERGE_DEGREES: f32 = 20.0;
+/// Minimum chroma of generated harmony colours, so they read as colours beside muted art.
+const HARMONY_CHROMA: f32 = 35.0;
+
+/// One colour of an extracted palette.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Swatch {
+    /// Gamma-encoded sRGB, each channel in 0..1.
+    pub color: Vec3,
+    /// Share of the palette's weight; measured colours weigh by the pixels they cover, harmony colours half their source.
+    pub weight: f32,
+    /// Whether the colour is a generated harmony rather than measured from the image.
+    pub generated: bool,
+}
+
+impl Swatch {
+    /// The colour with its weight in alpha, as album art palettes are drawn.
+    pub fn to_unorm(self) -> Unorm8x4 {
+        Unorm8x4::from_vec4(self.color.extend(self.weight.max(1.0 / 255.0)))
+    }
+}
+
+struct Measured {
+    color: Lch,
+    weight: f32,
+    generated: bool,
+}
+
+/// The [`PALETTE_COLORS`] swatches that best represent `image`, or `None` when it has no opaque pixels.
+///
+/// Opaque pixels whose channels spread more than `options.min_spread`, with at least `options.min_chroma` inside `options.lightness`,
+/// are split by median cut.
+/// When harmony is enabled, similar hues merge and analogous colours fill the gaps, ordered dark to light.
+/// Images with no such pixels, such as greyscale art, use every opaque pixel and never generate harmony.
+pub fn extract_palette(image: &RgbaImage, options: &PaletteOptions) -> Option<[Swatch; PALETTE_COLORS]> {
+    let to_lab = |pixel: &Rgba<u8>| -> Lab { Srgb::new(pixel[0], pixel[1], pixel[2]).into_format::<f32>().into_color() };
+    let opaque = || image.pixels().filter(|pixel| pixel[3] >= 128).map(to_lab);
+    let [min_lightness, max_lightness] = options.lightness;
+    let mut pixels = image
+        .pixels()
+        .filter(|pixel| pixel[3] >= 128 && pixel[0].max(pixel[1]).max(pixel[2]) - pixel[0].min(pixel[1]).min(pixel[2]) > options.min_spread)
+        .map(to_lab)
+        .filter(|&color| {
+            let chroma = IntoColor::<Lch>::into_color(color).chroma;
+            chroma >= options.min_chroma && (min_lightness..=max_lightness).contains(&color.l)
+        })
+        .collect::<Vec<_>>();
+    let colorful = !pixels.is_empty();
+    if !colorful {
+        pixels.extend(opaque());
+    }
+    if pixels.is_empty() {
+        return None;
+    }
+
+    let mut colors = dominant_colors(&mut pixels);
+    if colorful && options.harmony {
+        complete_palette(&mut colors);
+    }
+    let total = colors.iter().map(|measured| measured.weight).sum::<f32>();
+    Some(array::from_fn(|index| {
+        let measured = &colors[index % colors.len()];
+        let rgb: Srgb = measured.color.into_color();
+        let rgb = rgb.clamp();
+        Swatch {
+            color: Vec3::new(rgb.red, rgb.green, rgb.blue),
+            weight: measured.weight / total,
+            generated: measured.generated,
+        }
+    }))
+}
+
+/// Merges similar hues, then fills the palette with analogous colours of the strongest ones.
+fn complete_palette(colors: &mut ArrayVec<Measured, PALETTE_COLORS>) {
+    colors.sort_by(|a, b| b.weight.total_cmp(&a.weight));
+    let mut index = 1;
+    while index < colors.len() {
+        let (color, weight) = (colors[index].color, colors[index].weight);
+        if let Some(duplicate) = colors[..index]
+            .iter()
+            .position(|other| (color.hue - other.color.hue).into_degrees().abs() < HUE_MERGE_DEGREES)
+        {
+            colors[duplicate].weight += weight;
+            colors.remove(index);
+        } else {
+            index += 1;
+        }
+    }
+
+    let measured = colors.len();
+    for index in 0..PALETTE_COLORS - measured {
+        let source = &colors[index % measured];
+        let (lower, upper) = source.color.analogous();
+        let mut color = match index {
+            2 if measured == 1 => source.color.analogous_secondary().0,
+            index if index % 2 == 0 => lower,
+            _ => upper,
+        };
+        color.chroma = color.chroma.max(HARMONY_CHROMA);
+        let weight = source.weight * 0.5;
+        colors.push(Measured { color, weight, generated: true });
+    }
+    colors.sort_by(|a, b| a.color.l.total_cmp(&b.color.l));
+}
+
+const fn component(color: &Lab, channel: usize) -> f32 {
+    [color.l, color.a, color.b][channel]
+}
+
+/// Median cut: repeatedly halves the bucket with the widest spread along its widest channel.
+fn dominant_colors(pixels: &mut [Lab]) -> ArrayVec<Measured, PALETTE_COLORS> {
+    let mut buckets = ArrayVec::<Range<usize>, PALETTE_COLORS>::new();
+    buckets.push(0..pixels.len());
+
+    while buckets.len() < PALETTE_COLORS {
+        let Some((bucket_index, channel)) = buckets
+            .iter()
+            .enumerate()
+            .filter(|(_, range)| range.len() > 1)
+            .map(|(index, range)| {
+                let mut min = [f32::INFINITY; 3];
+                let mut max = [f32::NEG_INFINITY; 3];
+                for color in &pixels[range.clone()] {
+                    for channel in 0..3 {
+                        min[channel] = min[channel].min(component(color, channel));
+                        max[channel] = max[channel].max(component(color, channel));
+                    }
+                }
+                let (channel, spread) = (0..3).map(|channel| (channel, max[channel] - min[channel])).max_by(|a, b| a.1.total_cmp(&b.1)).unwrap();
+                (index, channel, spread * range.len() as f32)
+            })
+            .max_by(|a, b| a.2.total_cmp(&b.2))
+            .map(|(index, channel, _)| (index, channel))
+        else {
+            break;
+        };
+
+        let range = buckets.swap_remove(bucket_index);
+        pixels[range.clone()].sort_unstable_by(|a, b| component(a, channel).total_cmp(&component(b, channel)));
+        let middle = range.start + range.len() / 2;
+        buckets.push(range.start..middle);
+        buckets.push(middle..range.end);
+    }
+
+    buckets
+        .into_iter()
+        .map(|range| {
+            let weight = range.len() as f32;
+            let sum = pixels[range].iter().fold([0.0; 3], |mut sum, color| {
+                sum[0] += color.l;
+                sum[1] += color.a;
+                sum[2] += color.b;
+                sum
+            });
+            Measured {
+                color: Lab::new(sum[0] / weight, sum[1] / weight, sum[2] / weight).into_color(),
+                weight,
+                generated: false,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RED: [u8; 3] = [220, 30, 30];
+    const BLUE: [u8; 3] = [30, 60, 210];
+    const GREY: [u8; 3] = [128, 128, 128];
+    /// Chroma 21, but its channels spread only 30.
+    const MUTED_GREEN: [u8; 3] = [100, 130, 100];
+
+    /// A 16×16 image whose left `split` columns are `left` and the rest `right`.
+    fn halves(left: [u8; 3], right: [u8; 3], split: u32) -> RgbaImage {
+        RgbaImage::from_fn(16, 16, |x, _| {
+            let [r, g, b] = if x < split { left } else { right };
+            Rgba([r, g, b, 255])
+        })
+    }
+
+    fn near(color: Vec3, expected: [u8; 3]) -> bool {
+        (color - Vec3::from_array(expected.map(|channel| f32::from(channel) / 255.0))).abs().max_element() < 0.02
+    }
+
+    fn total_weight(swatches: &[Swatch]) -> f32 {
+        swatches.iter().map(|swatch| swatch.weight).sum()
+    }
+
+    #[test]
+    fn solid_colour_is_measured_and_completed_with_harmony() {
+        let swatches = extract_palette(&halves(RED, RED, 16), &PaletteOptions::default()).unwrap();
+        let measured = swatches.iter().filter(|swatch| !swatch.generated).collect::<Vec<_>>();
+        assert_eq!(measured.len(), 1);
+        assert!(near(measured[0].color, RED));
+        assert_eq!(swatches.iter().filter(|swatch| swatch.generated).count(), PALETTE_COLORS - 1);
+        assert!((total_weight(&swatches) - 1.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn weights_follow_coverage_without_harmony() {
+        let options = PaletteOptions {
+            harmony: false,
+            ..PaletteOptions::default()
+        };
+        let swatches = extract_palette(&halves(RED, BLUE, 4), &options).unwrap();
+        assert!(swatches.iter().all(|swatch| !swatch.generated));
+        let weight_of = |color| swatches.iter().filter(|swatch| near(swatch.color, color)).map(|swatch| swatch.weight).sum::<f32>();
+        assert!((weight_of(RED) - 0.25).abs() < 1e-4);
+        assert!((weight_of(BLUE) - 0.75).abs() < 1e-4);
+    }
+
+    #[test]
+    fn default_spread_ignores_grey_pixels() {
+        let swatches = extract_palette(&halves(RED, GREY, 2), &PaletteOptions::default()).unwrap();
+        assert!(swatches.iter().filter(|swatch| !swatch.generated).all(|swatch| near(swatch.color, RED)));
+    }
+
+    #[test]
+    fn defaults_keep_the_original_spread_threshold() {
+        let options = PaletteOptions {
+            harmony: false,
+            ..PaletteOptions::default()
+        };
+        let swatches = extract_palette(&halves(MUTED_GREEN, RED, 8), &options).unwrap();
+        assert!(swatches.iter().all(|swatch| near(swatch.color, RED)));
+
+        let options = PaletteOptions { min_spread: 29, ..options };
+        let swatches = extract_palette(&halves(MUTED_GREEN, RED, 8), &options).unwrap();
+        assert!(swatches.iter().any(|swatch| near(swatch.color, MUTED_GREEN)));
+    }
+
+    #[test]
+    fn minimum_chroma_ignores_muted_pixels() {
+        let options = PaletteOptions {
+            min_spread: 0,
+            min_chroma: 30.0,
+            harmony: false,
+            ..PaletteOptions::default()
+        };
+        let swatches = extract_palette(&halves(MUTED_GREEN, RED, 8), &options).unwrap();
+        assert!(swatches.iter().all(|swatch| near(swatch.color, RED)));
+    }
+
+    #[test]
+    fn lightness_range_limits_measured_colours() {
+        let dark = [90, 10, 10];
+        let options = PaletteOptions {
+            lightness: [40.0, 100.0],
+            harmony: false,
+            ..PaletteOptions::default()
+        };
+        let swatches = extract_palette(&halves(dark, RED, 8), &options).unwrap();
+        assert!(swatches.iter().all(|swatch| near(swatch.color, RED)));
+    }
+
+    #[test]
+    fn greyscale_art_falls_back_to_every_pixel() {
+        let swatches = extract_palette(&halves(GREY, [30, 30, 30], 8), &PaletteOptions::default()).unwrap();
+        assert!(swatches.iter().all(|swatch| !swatch.generated));
+        assert!(swatches.iter().any(|swatch| near(swatch.color, GREY)));
+        assert!((total_weight(&swatches) - 1.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn transparent_image_has_no_palette() {
+        assert!(extract_palette(&RgbaImage::new(8, 8), &PaletteOptions::default()).is_none());
+    }
+}
@@ -2,6 +2,7 @@ use crate::{
     app::{
         AppUpdater, Background, CantusApp,
         config::{Layer as ConfigLayer, LayerAnchor as ConfigLayerAnchor},
+        music::{AudioTap, Media, PlayerStream},
         send_update,
     },
     render::{
@@ -12,6 +13,7 @@ use crate::{
     },
 };
 use freedesktop_desktop_entry::{desktop_entries, get_languages_from_env};
+use futures_util::{StreamExt, stream};
 use isthmus::glam::vec2;
 use isthmus::wgpu::{
     Surface, SurfaceTargetUnsafe,
@@ -25,22 +27,25 @@ use std::{
     ffi::c_void,
     fs::{self, File},
     io::{self, Read, Write},
-    os::{fd::AsFd, unix::net::UnixDatagram as BlockingUnixDatagram},
+    mem,
+    os::{
+        fd::AsFd,
+        unix::{net::UnixDatagram as BlockingUnixDatagram, process::CommandExt},
+    },
     path::{Path, PathBuf},
     process::{self, Command, Stdio},
     ptr::NonNull,
-    sync::{
-        Arc,
-        atomic::{AtomicU32, Ordering},
-    },
+    sync::{Arc, atomic::Ordering},
     thread,
     time::{Duration, Instant},
 };
 use sysinfo::{Gpus, System};
-use tokio::net::UnixDatagram;
+use tokio::{net::UnixDatagram, time::sleep};
 use tracing::warn;
 use wayland_client::{
-    Connection, Dispatch, Proxy, QueueHandle, WEnum, delegate_noop, event_created_child,
+    Connection, Dispatch, Proxy, QueueHandle, WEnum,
+    backend::ObjectId,
+    delegate_noop, event_created_child,
     globals::{GlobalListContents, registry_queue_init},
     protocol::{
         wl_callback::{self, WlCallback},
@@ -68,38 +73,145 @@ use wayland_protocols::wp::{
     },
     viewporter::client::{wp_viewport::WpViewport, wp_viewporter::WpViewporter},
 };
-use wayland_protocols_wlr::layer_shell::v1::client::{
-    zwlr_layer_shell_v1::{Layer as LayerStyle, ZwlrLayerShellV1},
-    zwlr_layer_surface_v1::{self, Anchor as LayerAnchor, KeyboardInteractivity, ZwlrLayerSurfaceV1},
+use wayland_protocols_wlr::{
+    data_control::v1::client::{
+        zwlr_data_control_device_v1::{self, ZwlrDataControlDeviceV1},
+        zwlr_data_control_manager_v1::ZwlrDataControlManagerV1,
+        zwlr_data_control_offer_v1::{self, ZwlrDataControlOfferV1},
+    },
+    foreign_toplevel::v1::client::{
+        zwlr_foreign_toplevel_handle_v1::{self, ZwlrForeignToplevelHandleV1},
+        zwlr_foreign_toplevel_manager_v1::{self, ZwlrForeignToplevelManagerV1},
+    },
+    layer_shell::v1::client::{
+        zwlr_layer_shell_v1::{Layer as LayerStyle, ZwlrLayerShellV1},
+        zwlr_layer_surface_v1::{self, Anchor as LayerAnchor, KeyboardInteractivity, ZwlrLayerSurfaceV1},
+    },
 };
 use xkbcommon::xkb;
-use zbus::Connection as DbusConnection;
+use zbus::{
+    Connection as DbusConnection, MatchRule, MessageStream,
+    message::Type as MessageType,
+    zvariant::{OwnedValue, Value as Variant},
+};
 
 const AUDIO_SAMPLE_RATE: u32 = 48_000;
 const AUDIO_WINDOW_SIZE: usize = 1024;
 const AUDIO_BAND_EDGES: [f32; AUDIO_SPECTRUM_BANDS + 1] = [60.0, 120.0, 250.0, 500.0, 1_000.0, 2_000.0, 4_000.0, 12_000.0];
+/// Frequencies spanned by the visualiser's logarithmic bands.
+const VISUALISER_RANGE: (f32, f32) = (40.0, 16_000.0);
 const LAUNCHER_SOCKET_NAME: &str = "cantus-launcher.sock";
 const TEXT_MIME: &str = "text/plain;charset=utf-8";
+/// Marks a selection copied from a password manager, which stays out of the clipboard history.
+const SECRET_MIME: &str = "x-kde-passwordManagerHint";
+/// Larger selections are left out of the clipboard history.
+const MAX_CLIPBOARD_BYTES: u64 = 64 * 1024;
+const MPRIS_PREFIX: &str = "org.mpris.MediaPlayer2.";
+const MPRIS_PATH: &str = "/org/mpris/MediaPlayer2";
+const MPRIS_PLAYER: &str = "org.mpris.MediaPlayer2.Player";
+const APPLICATION_INTERFACE: &str = "org.freedesktop.Application";
+/// Wait before reconnecting after the session bus drops the media monitor.
+const MEDIA_RETRY_INTERVAL: Duration = Duration::from_secs(5);
+const COMMAND_POLL_INTERVAL: Duration = Duration::from_millis(10);
+/// Terminals tried in order when none is configured, with the template that runs a command in each.
+const TERMINALS: [(&str, &str); 10] = [
+    ("xdg-terminal-exec", "xdg-terminal-exec {cmd}"),
+    ("foot", "foot {cmd}"),
+    ("kitty", "kitty {cmd}"),
+    ("alacritty", "alacritty -e {cmd}"),
+    ("wezterm", "wezterm start -- {cmd}"),
+    ("ghostty", "ghostty -e {cmd}"),
+    ("gnome-terminal", "gnome-terminal -- {cmd}"),
+    ("konsole", "konsole -e {cmd}"),
+    ("xfce4-terminal", "xfce4-terminal -x {cmd}"),
+    ("xterm", "xterm -e {cmd}"),
+];
 
 /// One launchable desktop entry.
 pub struct DesktopApp {
+    /// Desktop file id, such as `org.mozilla.firefox`.
+    pub id: String,
+    /// `StartupWMClass`, which windows may report as their app id instead.
+    pub wm_class: Option<String>,
     pub name: String,
-    pub exec: String,
+    /// `Exec` split into arguments, with its field codes expanded; empty for apps only started over D-Bus.
+    pub exec: Vec<String>,
     pub comment: String,
+    /// Whether the app must run inside a terminal.
+    pub terminal: bool,
+    /// Whether the app is started through `org.freedesktop.Application` rather than by running `exec`.
+    pub dbus_activatable: bool,
+    /// `Path`, the working directory `exec` runs in.
+    pub directory: Option<PathBuf>,
     pub icon_path: Option<PathBuf>,
-    pub action: Option<(String, String)>,
+    /// Desktop actions; shift+enter runs the first.
+    pub actions: Vec<DesktopAction>,
     pub icon_layer: i32,
 }
 
+/// An extra way to start an app, such as a browser's private window.
+pub struct DesktopAction {
+    /// Action id, which D-Bus activation names it by.
+    pub id: String,
+    pub name: String,
+    pub exec: Vec<String>,
+}
+
+/// How the launcher starts apps.
+pub struct LaunchOptions {
+    /// Command line terminal apps run in, where a `{cmd}` argument stands for the app's command.
+    pub terminal: Option<String>,
+    /// Whether each app runs in its own systemd user scope.
+    pub systemd_scopes: bool,
+}
+
+/// What an `Exec` line's field codes expand to for one desktop entry.
+#[derive(Default)]
+struct ExecFields<'a> {
+    icon: Option<&'a str>,
+    name: &'a str,
+    file: &'a str,
+}
+
+/// A window open on the desktop, as reported by the compositor.
+pub struct OpenWindow {
+    /// Identifies the window to focus through `LauncherState::pending_focus`.
+    pub id: u32,
+    pub title: String,
+    pub app_id: String,
+}
+
 /// Host integration used by app and render code.
 pub trait Platform {
     const STATUS_SAMPLE_INTERVAL: Duration;
 
-    fn start_status_monitor(background: &Background, updater: AppUpdater, spectrum: Arc<[AtomicU32; AUDIO_SPECTRUM_BANDS]>);
-    fn set_volume(volume: f32);
+    fn start_status_monitor(background: &Background, updater: AppUpdater);
+    /// Captures the audio being played into `tap`'s spectrum and track analysis.
+    fn start_playback_capture(background: &Background, updater: &AppUpdater, tap: Arc<AudioTap>);
+    /// Follows the default sink and the stream of the player named `player`.
+    fn start_volume_monitor(background: &Background, updater: &AppUpdater, player: &str);
+    /// Sets a stream node's volume, or the default sink's without one.
+    fn set_volume(node: Option<u32>, volume: f32);
+    fn set_muted(node: Option<u32>, muted: bool);
+    /// Follows media playing in players other than `player`, for the queue's interruption pill.
+    fn start_media_monitor(background: &Background, updater: &AppUpdater, player: &str);
+    /// Plays or pauses the media player owning the bus name `player`.
+    fn toggle_media(background: &Background, player: &str);
     fn run_power_action(background: &Background, action: usize);
+    /// Shows a desktop notification, as critical when `urgent` so it stays until dismissed.
+    fn notify(background: &Background, summary: &str, body: &str, urgent: bool);
     fn desktop_apps() -> Vec<DesktopApp>;
-    fn spawn(exec: &str);
+    /// Starts `app`, or one of its actions: over D-Bus when the app is activatable, otherwise by running its command as `options` describe.
+    fn launch(background: &Background, app: &DesktopApp, action: Option<&DesktopAction>, options: &LaunchOptions);
+    /// Template for running a command in the user's terminal, with `{cmd}` standing for the command.
+    fn terminal_template() -> Option<String>;
+    /// Font files that cover emoji, best match first.
+    fn emoji_fonts() -> Vec<PathBuf>;
+    /// Runs a shell command line, detached.
+    fn run_command(command: &str);
+    /// Runs a shell command with `argument` as `$1` and collects its stdout, or `None` when it fails, outlives `timeout`
+    /// or is killed once `cancelled` returns true.
+    fn command_output(command: &str, argument: &str, timeout: Duration, cancelled: impl Fn() -> bool) -> Option<Vec<u8>>;
     fn open_url(url: &str);
     fn start_launcher_listener(background: &Background, updater: &AppUpdater);
     fn trigger_launcher() -> !;
@@ -112,20 +224,62 @@ pub type Current = Linux;
 impl Platform for Linux {
     const STATUS_SAMPLE_INTERVAL: Duration = Duration::from_millis(500);
 
-    fn start_status_monitor(background: &Background, updater: AppUpdater, spectrum: Arc<[AtomicU32; AUDIO_SPECTRUM_BANDS]>) {
-        let volume_updater = updater.clone();
-        background.run(move || monitor_playback(&spectrum));
-        background.run(move || monitor_volume(&volume_updater));
+    fn start_status_monitor(background: &Background, updater: AppUpdater) {
         background.run(move || monitor_status(&updater));
     }
 
-    fn set_volume(volume: f32) {
+    fn start_playback_capture(background: &Background, updater: &AppUpdater, tap: Arc<AudioTap>) {
+        let updater = updater.clone();
+        background.run(move || monitor_playback(&tap, &updater));
+    }
+
+    fn start_volume_monitor(background: &Background, updater: &AppUpdater, player: &str) {
+        let (updater, player) = (updater.clone(), player.to_owned());
+        background.run(move || monitor_volume(&updater, &player));
+    }
+
+    fn set_volume(node: Option<u32>, volume: f32) {
         let volume = format!("{volume:.3}");
-        if let Err(error) = Command::new("wpctl").args(["set-volume", "@DEFAULT_AUDIO_SINK@", &volume]).spawn() {
+        if let Err(error) = Command::new("wpctl").args(["set-volume", &volume_target(node), &volume]).spawn() {
             warn!(%error, "Failed to set PipeWire volume");
         }
     }
 
+    fn set_muted(node: Option<u32>, muted: bool) {
+        if let Err(error) = Command::new("wpctl").args(["set-mute", &volume_target(node), if muted { "1" } else { "0" }]).spawn() {
+            warn!(%error, "Failed to set PipeWire mute");
+        }
+    }
+
+    fn start_media_monitor(background: &Background, updater: &AppUpdater, player: &str) {
+        let (updater, player) = (updater.clone(), player.to_lowercase());
+        background.spawn(async move {
+            while let Err(error) = monitor_media(&updater, &player).await {
+                warn!(%error, "MPRIS media monitor stopped");
+                sleep(MEDIA_RETRY_INTERVAL).await;
+            }
+            None
+        });
+    }
+
+    fn toggle_media(background: &Background, player: &str) {
+        let player = player.to_owned();
+        background.spawn(async move {
+            let result = async {
+                DbusConnection::session()
+                    .await?
+                    .call_method(Some(player.as_str()), MPRIS_PATH, Some(MPRIS_PLAYER), "PlayPause", &())
+                    .await?;
+                Ok::<_, zbus::Error>(())
+            }
+            .await;
+            if let Err(error) = result {
+                warn!(%error, player, "Failed to toggle media playback");
+            }
+            None
+        });
+    }
+
     /// Calls logind directly, which is what `systemctl poweroff` does under the hood.
     fn run_power_action(background: &Background, action: usize) {
         let method = ["PowerOff", "Reboot"][action];
@@ -151,40 +305,167 @@ impl Platform for Linux {
         });
     }
 
+    fn notify(background: &Background, summary: &str, body: &str, urgent: bool) {
+        let (summary, body) = (summary.to_owned(), body.to_owned());
+        background.spawn(async move {
+            let result = async {
+                let hints = HashMap::from([("urgency", Variant::from(if urgent { 2u8 } else { 1 }))]);
+                DbusConnection::session()
+                    .await?
+                    .call_method(
+                        Some("org.freedesktop.Notifications"),
+                        "/org/freedesktop/Notifications",
+                        Some("org.freedesktop.Notifications"),
+                        "Notify",
+                        &("cantus", 0u32, "alarm-symbolic", summary.as_str(), body.as_str(), Vec::<&str>::new(), hints, -1i32),
+                    )
+                    .await?;
+                Ok::<_, zbus::Error>(())
+            }
+            .await;
+            if let Err(error) = result {
+                warn!(%error, summary, "Failed to show notification");
+            }
+            None
+        });
+    }
+
     fn desktop_apps() -> Vec<DesktopApp> {
         let mut seen = HashSet::new();
         let locales = get_languages_from_env();
         desktop_entries(&locales)
             .into_iter()
             .filter(|entry| seen.insert(entry.id().to_owned()))
-            .filter(|entry| !entry.no_display() && !entry.hidden() && !entry.terminal())
+            .filter(|entry| !entry.no_display() && !entry.hidden())
             .filter_map(|entry| {
-                let action = entry
+                let name = entry.name(&locales)?.into_owned();
+                let dbus_activatable = entry.desktop_entry("DBusActivatable") == Some("true");
+                let file = entry.path.to_string_lossy();
+                let fields = ExecFields {
+                    icon: entry.icon(),
+                    name: &name,
+                    file: &file,
+                };
+                // Activatable apps may leave out `Exec`; any other app without one can't be started.
+                let exec = entry.exec().and_then(|exec| exec_arguments(exec, &fields)).unwrap_or_default();
+                if exec.is_empty() && !dbus_activatable {
+                    return None;
+                }
+                let actions = entry
                     .actions()
-                    .and_then(|actions| actions.into_iter().find(|action| !action.is_empty()))
-                    .and_then(|action| entry.action_entry_localized(action, "Name", &locales).zip(entry.action_entry(action, "Exec")))
-                    .map(|(name, exec)| (name.into_owned(), exec.to_owned()));
+                    .unwrap_or_default()
+                    .into_iter()
+                    .filter(|action| !action.is_empty())
+                    .filter_map(|action| {
+                        let exec = entry.action_entry(action, "Exec").and_then(|exec| exec_arguments(exec, &fields)).unwrap_or_default();
+                        Some(DesktopAction {
+                            id: action.to_owned(),
+                            name: entry.action_entry_localized(action, "Name", &locales)?.into_owned(),
+                            exec,
+                        })
+                        .filter(|action| !action.exec.is_empty() || dbus_activatable)
+                    })
+                    .collect();
                 Some(DesktopApp {
-                    name: entry.name(&locales)?.into_owned(),
-                    exec: entry.exec()?.to_owned(),
+                    id: entry.id().to_owned(),
+                    wm_class: entry.startup_wm_class().map(str::to_owned),
+                    name,
+                    exec,
                     comment: entry.comment(&locales).unwrap_or_default().into_owned(),
+                    terminal: entry.terminal(),
+                    dbus_activatable,
+                    directory: entry.desktop_entry("Path").filter(|path| !path.is_empty()).map(PathBuf::from),
                     icon_path: entry.icon().and_then(resolve_icon),
-                    action,
+                    actions,
                     icon_layer: -1,
                 })
             })
             .collect()
     }
 
-    /// Strips desktop-entry field codes (`%f %F %u %U %i %c %k`) and launches the command, detached.
-    fn spawn(exec: &str) {
-        let mut args = exec.split_whitespace().filter(|token| !token.starts_with('%'));
-        let Some(program) = args.next() else { return };
-        if let Err(error) = Command::new(program).args(args).stdin(Stdio::null()).stdout(Stdio::null()).stderr(Stdio::null()).spawn() {
-            warn!(%error, program, "Failed to launch application");
+    fn terminal_template() -> Option<String> {
+        if let Some(terminal) = env::var("TERMINAL").ok().filter(|terminal| !terminal.trim().is_empty()) {
+            return Some(format!("{terminal} -e {{cmd}}"));
+        }
+        let path = env::var_os("PATH").unwrap_or_default();
+        let installed = |program: &str| env::split_paths(&path).any(|directory| directory.join(program).is_file());
+        TERMINALS.into_iter().find(|(program, _)| installed(program)).map(|(_, template)| template.to_owned())
+    }
+
+    fn emoji_fonts() -> Vec<PathBuf> {
+        match Command::new("fc-list").args(["--format", "%{file}\n", ":charset=1f600"]).output() {
+            Ok(output) => String::from_utf8_lossy(&output.stdout).lines().map(PathBuf::from).collect(),
+            Err(error) => {
+                warn!(%error, "Failed to list fonts");
+                Vec::new()
+            }
+        }
+    }
+
+    fn launch(background: &Background, app: &DesktopApp, action: Option<&DesktopAction>, options: &LaunchOptions) {
+        let exec = action.map_or(&app.exec, |action| &action.exec);
+        let command = (!exec.is_empty()).then(|| app_command(app, exec, options)).flatten();
+        if !app.dbus_activatable {
+            spawn_app(command, &app.name);
+            return;
+        }
+        let (id, action, name) = (app.id.clone(), action.map(|action| action.id.clone()), app.name.clone());
+        background.spawn(async move {
+            // Fall back to `Exec` when the app doesn't answer on the bus.
+            if let Err(error) = activate_app(&id, action.as_deref()).await {
+                warn!(%error, app = name, "Failed to activate app over D-Bus");
+                spawn_app(command, &name);
+            }
+            None
+        });
+    }
+
+    fn run_command(command: &str) {
+        if let Err(error) = Command::new("sh")
+            .args(["-c", command])
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+        {
+            warn!(%error, command, "Failed to run command");
         }
     }
 
+    fn command_output(command: &str, argument: &str, timeout: Duration, cancelled: impl Fn() -> bool) -> Option<Vec<u8>> {
+        let mut child = Command::new("sh")
+            .args(["-c", command, "cantus", argument])
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            // Its own process group, so whatever the shell starts is killed along with it.
+            .process_group(0)
+            .spawn()
+            .inspect_err(|error| warn!(%error, command, "Failed to run command"))
+            .ok()?;
+        let mut stdout = child.stdout.take()?;
+        // Read while waiting, so output larger than the pipe buffer can't stall the command.
+        let reader = thread::spawn(move || {
+            let mut output = Vec::new();
+            stdout.read_to_end(&mut output).map(|_| output)
+        });
+        let started = Instant::now();
+        while child.try_wait().ok()?.is_none() {
+            let timed_out = started.elapsed() >= timeout;
+            if timed_out || cancelled() {
+                kill_group(&mut child);
+                // The pipe closes once the whole group is gone.
+                let _ = reader.join();
+                if timed_out {
+                    warn!(command, "Command timed out");
+                }
+                return None;
+            }
+            thread::sleep(COMMAND_POLL_INTERVAL);
+        }
+        reader.join().ok()?.ok()
+    }
+
     fn open_url(url: &str) {
         if let Err(error) = Command::new("xdg-open").arg(url).spawn() {
             warn!(%error, %url, "Failed to open URL");
@@ -224,6 +505,175 @@ impl Platform for Linux {
     }
 }
 
+/// Splits a desktop entry's `Exec` value into arguments as the Desktop Entry Specification describes: string escapes are
+/// undone first, then double-quoted arguments, then field codes. Launching never passes files or URLs, so an argument
+/// holding one of their codes is left out; an unterminated quote makes the whole value invalid.
+fn exec_arguments(exec: &str, fields: &ExecFields) -> Option<Vec<String>> {
+    let mut line = String::with_capacity(exec.len());
+    let mut characters = exec.chars();
+    while let Some(character) = characters.next() {
+        match (character, characters.clone().next()) {
+            ('\\', Some(escaped @ ('s' | 'n' | 't' | 'r' | '\\'))) => {
+                characters.next();
+                line.push(match escaped {
+                    's' => ' ',
+                    'n' => '\n',
+                    't' => '\t',
+                    'r' => '\r',
+                    _ => '\\',
+                });
+            }
+            (character, _) => line.push(character),
+        }
+    }
+
+    let mut arguments = Vec::new();
+    let mut argument = String::new();
+    // Whether `argument` has begun, which a pair of empty quotes does too, and whether a file or URL code removes it.
+    let (mut started, mut dropped) = (false, false);
+    let mut characters = line.chars();
+    while let Some(character) = characters.next() {
+        match character {
+            ' ' | '\t' | '\n' => {
+                if mem::take(&mut started) && !dropped {
+                    arguments.push(mem::take(&mut argument));
+                }
+                argument.clear();
+                dropped = false;
+            }
+            '"' => {
+                started = true;
+                loop {
+                    match characters.next()? {
+                        '"' => break,
+                        '\\' => match characters.next()? {
+                            escaped @ ('"' | '`' | '$' | '\\') => argument.push(escaped),
+                            other => argument.extend(['\\', other]),
+                        },
+                        other => argument.push(other),
+                    }
+                }
+            }
+            '%' => match characters.next() {
+                Some('%') | None => {
+                    started = true;
+                    argument.push('%');
+                }
+                Some('i') => {
+                    if let Some(icon) = fields.icon.filter(|icon| !icon.is_empty()) {
+                        if !started {
+                            arguments.push(String::from("--icon"));
+                        }
+                        started = true;
+                        argument.push_str(icon);
+                    }
+                }
+                Some('c') => {
+                    started = true;
+                    argument.push_str(fields.name);
+                }
+                Some('k') => {
+                    started = true;
+                    argument.push_str(fields.file);
+                }
+                Some('f' | 'F' | 'u' | 'U') => dropped = true,
+                // The deprecated codes.
+                Some(_) => {}
+            },
+            other => {
+                started = true;
+                argument.push(other);
+            }
+        }
+    }
+    if started && !dropped {
+        arguments.push(argument);
+    }
+    Some(arguments)
+}
+
+/// The process that runs `exec` for `app`: inside the terminal when it needs one, in its own scope when enabled.
+fn app_command(app: &DesktopApp, exec: &[String], options: &LaunchOptions) -> Option<Command> {
+    let mut arguments = if app.terminal {
+        let Some(template) = options.terminal.as_deref().and_then(|template| exec_arguments(template, &ExecFields::default())) else {
+            warn!(app = app.name, "No terminal found to run app in; set `terminal` in the config");
+            return None;
+        };
+        in_terminal(template, exec)
+    } else {
+        exec.to_vec()
+    };
+    if options.systemd_scopes {
+        // Named as the XDG guidelines for systemd ask, so session tools can tell which app a scope holds.
+        let unit = format!("--unit=app-cantus-{}-{}.scope", systemd_escape(&app.id), fastrand::u32(..));
+        let scope = ["systemd-run", "--user", "--scope", "--quiet", "--collect", "--slice=app.slice", &unit, "--"];
+        arguments.splice(0..0, scope.map(str::to_owned));
+    }
+    let (program, arguments) = arguments.split_first()?;
+    let mut command = Command::new(program);
+    command.args(arguments).stdin(Stdio::null()).stdout(Stdio::null()).stderr(Stdio::null());
+    if let Some(directory) = &app.directory {
+        command.current_dir(directory);
+    }
+    Some(command)
+}
+
+/// Puts `exec` where the terminal template's `{cmd}` argument is, or after the template when it has none.
+fn in_terminal(template: Vec<String>, exec: &[String]) -> Vec<String> {
+    if !template.iter().any(|argument| argument.contains("{cmd}")) {
+        return [template, exec.to_vec()].concat();
+    }
+    template
+        .into_iter()
+        .flat_map(|argument| match argument.as_str() {
+            "{cmd}" => exec.to_vec(),
+            // Inside a longer argument, such as `sh -c "{cmd}"`, the command goes in as one line.
+            _ => vec![argument.replace("{cmd}", &exec.join(" "))],
+        })
+        .collect()
+}
+
+/// Escapes `id` as `systemd-escape` does, so its dashes don't read as separators in a unit name.
+fn systemd_escape(id: &str) -> String {
+    id.bytes()
+        .enumerate()
+        .map(|(index, byte)| match byte {
+            b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b':' | b'_' => char::from(byte).to_string(),
+            b'.' if index > 0 => String::from("."),
+            _ => format!("\\x{byte:02x}"),
+        })
+        .collect()
+}
+
+fn spawn_app(command: Option<Command>, name: &str) {
+    if let Some(mut command) = command
+        && let Err(error) = command.spawn()
+    {
+        warn!(%error, app = name, "Failed to launch application");
+    }
+}
+
+/// Starts or raises a `DBusActivatable` app, or runs one of its actions, through `org.freedesktop.Application`.
+async fn activate_app(id: &str, action: Option<&str>) -> zbus::Result<()> {
+    let path = format!("/{}", id.replace('.', "/").replace('-', "_"));
+    let connection = DbusConnection::session().await?;
+    let platform_data = HashMap::<&str, Variant>::new();
+    match action {
+        Some(action) => {
+            let parameters = Vec::<Variant>::new();
+            connection
+                .call_method(Some(id), path.as_str(), Some(APPLICATION_INTERFACE), "ActivateAction", &(action, parameters, platform_data))
+                .await?
+        }
+        None => {
+            connection
+                .call_method(Some(id), path.as_str(), Some(APPLICATION_INTERFACE), "Activate", &(platform_data,))
+                .await?
+        }
+    };
+    Ok(())
+}
+
 fn launcher_socket_path() -> PathBuf {
     let runtime_dir = env::var_os("XDG_RUNTIME_DIR").unwrap_or_else(|| "/tmp".into());
     PathBuf::from(runtime_dir).join(LAUNCHER_SOCKET_NAME)
@@ -315,55 +765,95 @@ fn battery_sample(path: Option<&Path>) -> f32 {
     }
 }
 
-fn monitor_playback(levels: &[AtomicU32; AUDIO_SPECTRUM_BANDS]) {
+fn monitor_playback(tap: &AudioTap, updater: &AppUpdater) {
     loop {
-        if let Err(error) = capture_playback(levels) {
+        if let Err(error) = capture_playback(tap, updater) {
             warn!(%error, "PipeWire playback meter stopped");
         }
-        for level in levels {
-            level.store(0.0f32.to_bits(), Ordering::Relaxed);
-        }
+        tap.silence();
         thread::sleep(Duration::from_secs(1));
     }
 }
 
+fn volume_target(node: Option<u32>) -> String {
+    node.map_or_else(|| "@DEFAULT_AUDIO_SINK@".to_owned(), |node| node.to_string())
+}
+
 #[derive(Default)]
 struct PipeWireState {
     default_sink: Option<String>,
     sinks: HashMap<String, f32>,
+    /// Application name or process binary whose output stream is the player's.
+    player: String,
+    /// Volume of each of the player's output streams by node id; players may open several at once.
+    player_nodes: HashMap<u32, f32>,
+}
+
+enum VolumeChange {
+    Sink(f32),
+    Player(Option<PlayerStream>),
 }
 
 impl PipeWireState {
-    fn update(&mut self, object: &Value) -> Option<f32> {
+    fn update(&mut self, object: &Value) -> Option<VolumeChange> {
         if let Some(metadata) = object["metadata"]
             .as_array()
             .and_then(|items| items.iter().find(|item| item["key"] == "default.audio.sink"))
         {
             self.default_sink = metadata["value"]["name"].as_str().map(str::to_owned);
-            return self.sinks.get(self.default_sink.as_ref()?).copied();
+            return self.sinks.get(self.default_sink.as_ref()?).copied().map(VolumeChange::Sink);
         }
+        let node = object["id"].as_u64().and_then(|id| u32::try_from(id).ok());
         let info = &object["info"];
-        if info["props"]["media.class"] == "Audio/Sink"
-            && let Some(name) = info["props"]["node.name"].as_str()
-            && let Some(props) = info["params"]["Props"]
-                .as_array()
-                .and_then(|items| items.iter().find(|props| props["channelVolumes"].is_array()))
-        {
-            let volumes = props["channelVolumes"].as_array().unwrap();
-            let mut volume = (volumes.iter().filter_map(Value::as_f64).sum::<f64>() / volumes.len().max(1) as f64).cbrt() as f32;
-            if props["mute"].as_bool().unwrap_or_default() {
-                volume = -volume;
+        // Removed objects are reported with their id and a null info.
+        if info.is_null() {
+            self.player_nodes.remove(&node?)?;
+            return Some(VolumeChange::Player(self.player_stream()));
+        }
+        let props = &info["props"];
+        match props["media.class"].as_str()? {
+            "Audio/Sink" => {
+                let name = props["node.name"].as_str()?;
+                let volume = node_volume(info)?;
+                self.sinks.insert(name.to_owned(), volume);
+                (Some(name) == self.default_sink.as_deref()).then_some(VolumeChange::Sink(volume))
             }
-            self.sinks.insert(name.to_owned(), volume);
-            return (Some(name) == self.default_sink.as_deref()).then_some(volume);
+            "Stream/Output/Audio" if self.is_player(props) => {
+                let node = node?;
+                let volume = node_volume(info)?;
+                self.player_nodes.insert(node, volume);
+                Some(VolumeChange::Player(Some(PlayerStream { node, volume })))
+            }
+            _ => None,
         }
-        None
+    }
+
+    /// The newest of the player's remaining streams, or `None` once it has none.
+    fn player_stream(&self) -> Option<PlayerStream> {
+        self.player_nodes
+            .iter()
+            .max_by_key(|(node, _)| **node)
+            .map(|(&node, &volume)| PlayerStream { node, volume })
+    }
+
+    fn is_player(&self, props: &Value) -> bool {
+        ["application.name", "application.process.binary"]
+            .into_iter()
+            .any(|key| props[key].as_str().is_some_and(|value| value.eq_ignore_ascii_case(&self.player)))
     }
 }
 
-fn monitor_volume(updater: &AppUpdater) {
+/// Cube-root volume averaged over a node's channels, negated while muted.
+fn node_volume(info: &Value) -> Option<f32> {
+    let props = info["params"]["Props"].as_array()?.iter().find(|props| props["channelVolumes"].is_array())?;
+    let volumes = props["channelVolumes"].as_array()?;
+    let volume = (volumes.iter().filter_map(Value::as_f64).sum::<f64>() / volumes.len().max(1) as f64).cbrt() as f32;
+    Some(if props["mute"].as_bool().unwrap_or_default() { -volume } else { volume })
+}
+
+fn monitor_volume(updater: &AppUpdater, player: &str) {
     loop {
-        match capture_volume(updater) {
+        match capture_volume(updater, player) {
             Ok(false) => break,
             Err(error) => warn!(%error, "PipeWire volume monitor stopped"),
             Ok(true) => {}
@@ -372,19 +862,154 @@ fn monitor_volume(updater: &AppUpdater) {
     }
 }
 
+/// Follows MPRIS players other than `primary`, reporting the one playing, or the followed one while it stays paused.
+/// Players are rescanned whenever one appears, leaves, or signals a change.
+async fn monitor_media(updater: &AppUpdater, primary: &str) -> zbus::Result<()> {
+    let connection = DbusConnection::session().await?;
+    let owners = MatchRule::builder()
+        .msg_type(MessageType::Signal)
+        .sender("org.freedesktop.DBus")?
+        .interface("org.freedesktop.DBus")?
+        .member("NameOwnerChanged")?
+        .build();
+    // Covers `PropertiesChanged` and `Seeked`, which players send from the same path.
+    let changes = MatchRule::builder().msg_type(MessageType::Signal).path(MPRIS_PATH)?.build();
+    let mut signals = stream::select(
+        MessageStream::for_match_rule(owners, &connection, None).await?,
+        MessageStream::for_match_rule(changes, &connection, None).await?,
+    );
+    let mut followed = None::<String>;
+    loop {
+        let names = connection
+            .call_method(Some("org.freedesktop.DBus"), "/org/freedesktop/DBus", Some("org.freedesktop.DBus"), "ListNames", &())
+            .await?
+            .body()
+            .deserialize::<Vec<String>>()?;
+        // playerctld mirrors whichever player is active, the music service included.
+        let players = names.iter().filter(|name| {
+            name.strip_prefix(MPRIS_PREFIX)
+                .is_some_and(|player| !player.starts_with("playerctld") && !player.to_lowercase().starts_with(primary))
+        });
+        let mut chosen = None::<Media>;
+        for name in players {
+            let Ok(Some(media)) = player_media(&connection, name).await else {
+                continue;
+            };
+            // Playing beats paused, and the followed player beats others in the same state.
+            let rank = |media: &Media| (media.playing, followed.as_deref() == Some(media.player.as_str()));
+            if (media.playing || rank(&media).1) && chosen.as_ref().is_none_or(|chosen| rank(&media) > rank(chosen)) {
+                chosen = Some(media);
+            }
+        }
+        followed = chosen.as_ref().map(|media| media.player.clone());
+        let observed_at = Instant::now();
+        if !send_update(updater, move |app| {
+            if app.playback.set_interruption(chosen, observed_at) {
+                app.refresh_art();
+            }
+        }) {
+            return Ok(());
+        }
+        match signals.next().await {
+            Some(Ok(_)) => {}
+            Some(Err(error)) => return Err(error),
+            None => return Err(zbus::Error::Failure("session bus closed".into())),
+        }
+    }
+}
+
+/// The media `name` reports, or `None` while it is stopped.
+async fn player_media(connection: &DbusConnection, name: &str) -> zbus::Result<Option<Media>> {
+    let mut properties = connection
+        .call_method(Some(name), MPRIS_PATH, Some("org.freedesktop.DBus.Properties"), "GetAll", &(MPRIS_PLAYER,))
+        .await?
+        .body()
+        .deserialize::<HashMap<String, OwnedValue>>()?;
+    let playing = match mpris_text(properties.get("PlaybackStatus")).as_deref() {
+        Some("Playing") => true,
+        Some("Paused") => false,
+        _ => return Ok(None),
+    };
+    let metadata = properties
+        .remove("Metadata")
+        .and_then(|metadata| HashMap::<String, OwnedValue>::try_from(metadata).ok())
+        .unwrap_or_default();
+    let rate = match properties.get("Rate").map(|rate| &**rate) {
+        Some(Variant::F64(rate)) => *rate as f32,
+        _ => 1.0,
+    };
+    let micros_to_ms = |micros: i64| micros.max(0) as f32 / 1000.0;
+    // Without a title, name the media after its player, such as `firefox` for `firefox.instance_1_42`.
+    let player = name.trim_start_matches(MPRIS_PREFIX);
+    Ok(Some(Media {
+        player: name.to_owned(),
+        title: mpris_text(metadata.get("xesam:title")).unwrap_or_else(|| player.split('.').next().unwrap_or(player).to_owned()),
+        artist: mpris_text(metadata.get("xesam:artist")).unwrap_or_default(),
+        art_url: mpris_text(metadata.get("mpris:artUrl")),
+        length_ms: mpris_micros(metadata.get("mpris:length")).map_or(0, |micros| micros_to_ms(micros) as u32),
+        position_ms: mpris_micros(properties.get("Position")).map_or(0.0, micros_to_ms),
+        rate: if playing { rate } else { 0.0 },
+        playing,
+    }))
+}
+
+/// A string property, with string lists such as `xesam:artist` joined by commas.
+fn mpris_text(value: Option<&OwnedValue>) -> Option<String> {
+    let text = match &**value? {
+        Variant::Str(text) => text.to_string(),
+        Variant::Array(items) => items
+            .iter()
+            .filter_map(|item| match item {
+                Variant::Str(text) => Some(text.as_str()),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+            .join(", "),
+        _ => return None,
+    };
+    (!text.is_empty()).then_some(text)
+}
+
+/// A microsecond property; players disagree on its integer type.
+fn mpris_micros(value: Option<&OwnedValue>) -> Option<i64> {
+    match **value? {
+        Variant::I64(micros) => Some(micros),
+        Variant::U64(micros) => i64::try_from(micros).ok(),
+        Variant::I32(micros) => Some(micros.into()),
+        Variant::U32(micros) => Some(micros.into()),
+        _ => None,
+    }
+}
+
+/// Kills `child` and every process in the group it leads.
+fn kill_group(child: &mut process::Child) {
+    let group = format!("-{}", child.id());
+    let killed = Command::new("kill").args(["-s", "KILL", "--", &group]).stderr(Stdio::null()).status();
+    if !killed.is_ok_and(|status| status.success()) {
+        let _ = child.kill();
+    }
+    let _ = child.wait();
+}
+
 fn piped(command: &mut Command) -> io::Result<(process::Child, process::ChildStdout)> {
     let mut child = command.stdout(Stdio::piped()).stderr(Stdio::null()).spawn()?;
     let output = child.stdout.take().ok_or_else(|| io::Error::other("command stdout was not piped"))?;
     Ok((child, output))
 }
 
-fn capture_volume(updater: &AppUpdater) -> io::Result<bool> {
+fn capture_volume(updater: &AppUpdater, player: &str) -> io::Result<bool> {
     let (mut child, output) = piped(Command::new("pw-dump").args(["--monitor", "--no-colors", "--indent", "0"]))?;
-    let mut state = PipeWireState::default();
+    let mut state = PipeWireState {
+        player: player.to_owned(),
+        ..PipeWireState::default()
+    };
     for batch in serde_json::Deserializer::from_reader(output).into_iter::<Vec<Value>>() {
         for object in batch.map_err(io::Error::other)? {
-            if let Some(volume) = state.update(&object)
-                && !send_update(updater, move |app| app.status_pass().pill.volume = volume)
+            if let Some(change) = state.update(&object)
+                && !send_update(updater, move |app| match change {
+                    VolumeChange::Sink(volume) => app.playback.mixer.sink = volume,
+                    VolumeChange::Player(stream) => app.playback.mixer.player = stream,
+                })
             {
                 child.kill()?;
                 child.wait()?;
@@ -396,7 +1021,7 @@ fn capture_volume(updater: &AppUpdater) -> io::Result<bool> {
     Ok(true)
 }
 
-fn capture_playback(levels: &[AtomicU32; AUDIO_SPECTRUM_BANDS]) -> io::Result<()> {
+fn capture_playback(tap: &AudioTap, updater: &AppUpdater) -> io::Result<()> {
     let (mut child, mut output) = piped(Command::new("pw-record").args([
         "--properties",
         "stream.capture.sink=true",
@@ -416,19 +1041,40 @@ fn capture_playback(levels: &[AtomicU32; AUDIO_SPECTRUM_BANDS]) -> io::Result<()
             Err(error) if error.kind() == io::ErrorKind::UnexpectedEof => break,
             Err(error) => return Err(error),
         }
+        // The transform runs in place, so the loudness and waveform are taken first.
+        let loudness = (window.iter().map(|sample| sample * sample).sum::<f32>() / AUDIO_WINDOW_SIZE as f32).sqrt();
+        let chunk = AUDIO_WINDOW_SIZE / tap.waveform.len().max(1);
+        for (point, samples) in tap.waveform.iter().zip(window.chunks(chunk)) {
+            point.store((samples.iter().sum::<f32>() / chunk as f32).to_bits(), Ordering::Relaxed);
+        }
         let spectrum = rfft_1024(&mut window);
-        for (band, level) in levels.iter().enumerate() {
-            let bin = |frequency: f32| (frequency * AUDIO_WINDOW_SIZE as f32 / AUDIO_SAMPLE_RATE as f32).ceil() as usize;
-            let bins = &spectrum[bin(AUDIO_BAND_EDGES[band])..bin(AUDIO_BAND_EDGES[band + 1])];
-            let rms = (bins.iter().map(microfft::Complex32::norm_sqr).sum::<f32>() / bins.len() as f32 / AUDIO_WINDOW_SIZE as f32).sqrt();
-            let value = ((20.0 * rms.log10() + 30.0) / 30.0).clamp(0.0, 1.0);
-            level.store(value.to_bits(), Ordering::Relaxed);
+        for (band, level) in tap.spectrum.iter().enumerate() {
+            level.store(band_level(spectrum, AUDIO_BAND_EDGES[band], AUDIO_BAND_EDGES[band + 1]).to_bits(), Ordering::Relaxed);
+        }
+        let (low, high) = VISUALISER_RANGE;
+        let edge = |band: usize| low * (high / low).powf(band as f32 / tap.detail.len() as f32);
+        for (band, level) in tap.detail.iter().enumerate() {
+            level.store(band_level(spectrum, edge(band), edge(band + 1)).to_bits(), Ordering::Relaxed);
+        }
+        let magnitudes = spectrum.iter().map(|bin| bin.norm_sqr().sqrt());
+        if let Some((uri, features)) = tap.hear(loudness, magnitudes, AUDIO_WINDOW_SIZE as f32 / AUDIO_SAMPLE_RATE as f32) {
+            send_update(updater, move |app| app.apply_audio_estimate(&uri, features));
         }
     }
     child.wait()?;
     Ok(())
 }
 
+/// Level of the spectrum between two frequencies, mapped from -30..0 dBFS onto 0..1.
+fn band_level(spectrum: &[microfft::Complex32], low: f32, high: f32) -> f32 {
+    let bin = |frequency: f32| (frequency * AUDIO_WINDOW_SIZE as f32 / AUDIO_SAMPLE_RATE as f32).ceil() as usize;
+    // Low bands can be narrower than a bin; they take the nearest one.
+    let start = bin(low).min(spectrum.len() - 1);
+    let bins = &spectrum[start..bin(high).clamp(start + 1, spectrum.len())];
+    let rms = (bins.iter().map(microfft::Complex32::norm_sqr).sum::<f32>() / bins.len() as f32 / AUDIO_WINDOW_SIZE as f32).sqrt();
+    ((20.0 * rms.log10() + 30.0) / 30.0).clamp(0.0, 1.0)
+}
+
 /// Runs the Wayland application event loop.
 ///
 /// # Panics
@@ -451,8 +1097,16 @@ pub fn run() {
             let device = manager.get_data_device(&seat, &qhandle, ());
             (manager, device)
         }),
+        seat: Some(seat),
         ..LayerShellApp::default()
     };
+    // Open windows feed the launcher's window switcher, on compositors that list them.
+    let _ = globals.bind::<ZwlrForeignToplevelManagerV1, _, _>(&qhandle, 1..=3, ());
+    if app.cantus.config.clipboard_history > 0
+        && let Ok(manager) = globals.bind::<ZwlrDataControlManagerV1, _, _>(&qhandle, 1..=2, ())
+    {
+        manager.get_data_device(app.seat.as_ref().unwrap(), &qhandle, ());
+    }
 
     // Every output is bound so its name arrives; the configured monitor replaces the first one.
     let registry = globals.registry();
@@ -547,6 +1201,10 @@ struct LayerShellApp {
     /// The selection offer to read on paste, kept only while it advertises text.
     selection: Option<WlDataOffer>,
     offer_is_text: bool,
+    /// The latest selection seen through data control, read into the clipboard history.
+    watched_selection: Option<ZwlrDataControlOfferV1>,
+    /// What each data control offer advertises, kept until the selection naming it arrives.
+    watched_offers: HashMap<ObjectId, WatchedOffer>,
     output: Option<WlOutput>,
     pending_bar_surface: Option<Surface<'static>>,
     pending_launcher_surface: Option<Surface<'static>>,
@@ -554,6 +1212,8 @@ struct LayerShellApp {
     wl_surface: Option<WlSurface>,
     launcher_wl_surface: Option<WlSurface>,
     layer_surface: Option<ZwlrLayerSurfaceV1>,
+    /// Height last asked of the bar's layer surface, which changes as the karaoke panel opens and closes.
+    bar_height: u32,
     launcher_layer_surface: Option<ZwlrLayerSurfaceV1>,
     viewporter: Option<WpViewporter>,
     fractional_manager: Option<WpFractionalScaleManagerV1>,
@@ -565,10 +1225,27 @@ struct LayerShellApp {
     launcher_fractional: Option<WpFractionalScaleV1>,
     launcher_background_effect: Option<ExtBackgroundEffectSurfaceV1>,
     launcher_configured: bool,
+    seat: Option<WlSeat>,
+    /// Open windows, which the launcher lists to switch between.
+    toplevels: Vec<Toplevel>,
     bar_frame_callback: Option<WlCallback>,
     launcher_frame_callback: Option<WlCallback>,
 }
 
+/// The MIME types a data control offer advertised that decide whether it is recorded.
+#[derive(Default)]
+struct WatchedOffer {
+    is_text: bool,
+    is_secret: bool,
+}
+
+/// A window followed through the foreign-toplevel manager.
+struct Toplevel {
+    handle: ZwlrForeignToplevelHandleV1,
+    title: String,
+    app_id: String,
+}
+
 macro_rules! destroy_proxies {
     ($state:expr, $($field:ident),+ $(,)?) => {
         $(if let Some(proxy) = $state.$field.take() {
@@ -638,6 +1315,48 @@ impl LayerShellApp {
         Some(text)
     }
 
+    /// Hands the launcher the open windows, as of the latest `done` from each.
+    fn publish_windows(&mut self) {
+        let windows = self
+            .toplevels
+            .iter()
+            .map(|toplevel| OpenWindow {
+                id: toplevel.handle.id().protocol_id(),
+                title: toplevel.title.clone(),
+                app_id: toplevel.app_id.clone(),
+            })
+            .collect();
+        self.cantus.launcher.set_windows(windows);
+    }
+
+    /// Asks the compositor to raise and focus the window the launcher picked.
+    fn focus_window(&self, id: u32) {
+        let Some(seat) = &self.seat else { return };
+        if let Some(toplevel) = self.toplevels.iter().find(|toplevel| toplevel.handle.id().protocol_id() == id) {
+            toplevel.handle.activate(seat);
+        }
+    }
+
+    /// Reads a selection into the clipboard history off the Wayland thread, since this client may be the one serving it.
+    fn record_selection(&self, offer: &ZwlrDataControlOfferV1) {
+        let Ok((reader, writer)) = io::pipe() else { return };
+        offer.receive(TEXT_MIME.to_owned(), writer.as_fd());
+        drop(writer);
+        if offer.backend().upgrade().is_none_or(|backend| Connection::from_backend(backend).flush().is_err()) {
+            return;
+        }
+        let updater = self.cantus.updater.clone();
+        thread::spawn(move || {
+            let mut bytes = Vec::new();
+            if reader.take(MAX_CLIPBOARD_BYTES + 1).read_to_end(&mut bytes).is_ok()
+                && bytes.len() as u64 <= MAX_CLIPBOARD_BYTES
+                && let Ok(text) = String::from_utf8(bytes)
+            {
+                send_update(&updater, move |app| app.launcher.record_clipboard(text));
+            }
+        });
+    }
+
     fn create_render_surface(&self, wl_surface: &WlSurface) -> Surface<'static> {
         let display = self.display_handle.expect("missing Wayland display handle");
         let window = RawWindowHandle::Wayland(WaylandWindowHandle::new(
@@ -743,6 +1462,13 @@ impl LayerShellApp {
                 program.add_secondary_surface(surface, width, height).expect("launcher surface is incompatible");
             }
         }
+        let bar_height = self.cantus.bar_surface_size().1 as u32;
+        if bar_height != self.bar_height
+            && let Some(layer_surface) = &self.layer_surface
+        {
+            layer_surface.set_size(0, bar_height);
+            self.bar_height = bar_height;
+        }
         self.update_scale_and_viewport();
         self.update_blur_region(qhandle);
 
@@ -763,6 +1489,11 @@ impl LayerShellApp {
         if let Some(text) = self.cantus.launcher.pending_copy.take() {
             self.set_clipboard(&text, qhandle);
         }
+        if let Some(window) = self.cantus.launcher.pending_focus.take() {
+            // Drop the launcher's exclusive keyboard grab first, so focus lands on the window.
+            self.sync_launcher_surface(qhandle);
+            self.focus_window(window);
+        }
         self.update_input_region(qhandle);
         let bar = self.wl_surface.as_ref().unwrap().clone();
         if self.bar_frame_callback.is_none() {
@@ -958,6 +1689,95 @@ impl Dispatch<WlDataDevice, ()> for LayerShellApp {
     ]);
 }
 
+impl Dispatch<ZwlrForeignToplevelManagerV1, ()> for LayerShellApp {
+    fn event(
+        state: &mut Self,
+        _proxy: &ZwlrForeignToplevelManagerV1,
+        event: zwlr_foreign_toplevel_manager_v1::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+        match event {
+            zwlr_foreign_toplevel_manager_v1::Event::Toplevel { toplevel } => state.toplevels.push(Toplevel {
+                handle: toplevel,
+                title: String::new(),
+                app_id: String::new(),
+            }),
+            zwlr_foreign_toplevel_manager_v1::Event::Finished => {
+                state.toplevels.clear();
+                state.publish_windows();
+            }
+            _ => {}
+        }
+    }
+
+    event_created_child!(Self, ZwlrForeignToplevelManagerV1, [
+        zwlr_foreign_toplevel_manager_v1::EVT_TOPLEVEL_OPCODE => (ZwlrForeignToplevelHandleV1, ()),
+    ]);
+}
+
+dispatch!(ZwlrForeignToplevelHandleV1, |state, proxy, event, _qhandle| {
+    let Some(index) = state.toplevels.iter().position(|toplevel| toplevel.handle == *proxy) else {
+        return;
+    };
+    match event {
+        zwlr_foreign_toplevel_handle_v1::Event::Title { title } => state.toplevels[index].title = title,
+        zwlr_foreign_toplevel_handle_v1::Event::AppId { app_id } => state.toplevels[index].app_id = app_id,
+        zwlr_foreign_toplevel_handle_v1::Event::Done => state.publish_windows(),
+        zwlr_foreign_toplevel_handle_v1::Event::Closed => {
+            state.toplevels.remove(index).handle.destroy();
+            state.publish_windows();
+        }
+        _ => {}
+    }
+});
+
+impl Dispatch<ZwlrDataControlDeviceV1, ()> for LayerShellApp {
+    fn event(state: &mut Self, proxy: &ZwlrDataControlDeviceV1, event: zwlr_data_control_device_v1::Event, _data: &(), _conn: &Connection, _qhandle: &QueueHandle<Self>) {
+        match event {
+            zwlr_data_control_device_v1::Event::DataOffer { id } => {
+                state.watched_offers.insert(id.id(), WatchedOffer::default());
+            }
+            zwlr_data_control_device_v1::Event::Selection { id } => {
+                if let Some(stale) = state.watched_selection.take() {
+                    stale.destroy();
+                }
+                let watched = id.as_ref().and_then(|offer| state.watched_offers.remove(&offer.id()));
+                if let Some(offer) = id.as_ref().filter(|_| watched.is_some_and(|watched| watched.is_text && !watched.is_secret)) {
+                    state.record_selection(offer);
+                }
+                state.watched_selection = id;
+            }
+            zwlr_data_control_device_v1::Event::PrimarySelection { id: Some(offer) } => {
+                state.watched_offers.remove(&offer.id());
+                offer.destroy();
+            }
+            zwlr_data_control_device_v1::Event::Finished => {
+                if let Some(stale) = state.watched_selection.take() {
+                    stale.destroy();
+                }
+                state.watched_offers.clear();
+                proxy.destroy();
+            }
+            _ => {}
+        }
+    }
+
+    event_created_child!(Self, ZwlrDataControlDeviceV1, [
+        zwlr_data_control_device_v1::EVT_DATA_OFFER_OPCODE => (ZwlrDataControlOfferV1, ()),
+    ]);
+}
+
+dispatch!(ZwlrDataControlOfferV1, |state, proxy, event, _qhandle| {
+    if let zwlr_data_control_offer_v1::Event::Offer { mime_type } = event
+        && let Some(watched) = state.watched_offers.get_mut(&proxy.id())
+    {
+        watched.is_text |= mime_type == TEXT_MIME;
+        watched.is_secret |= mime_type == SECRET_MIME;
+    }
+});
+
 dispatch!(WlDataOffer, |state, _proxy, event, _qhandle| {
     if let wl_data_offer::Event::Offer { mime_type } = event {
         state.offer_is_text |= mime_type == TEXT_MIME;
@@ -1111,6 +1931,9 @@ dispatch!(WlPointer, |state, _proxy, event, _qhandle| {
                 interaction.cancel_drag();
                 interaction.hover();
             }
+            (0x112, WEnum::Value(wl_pointer::ButtonState::Pressed)) => {
+                interaction.middle_press();
+            }
             _ => {}
         },
         wl_pointer::Event::AxisDiscrete {
@@ -1141,5 +1964,104 @@ delegate_noop!(LayerShellApp: ignore WpViewport);
 delegate_noop!(LayerShellApp: ignore WlCompositor);
 delegate_noop!(LayerShellApp: ignore WlRegion);
 delegate_noop!(LayerShellApp: ignore WlDataDeviceManager);
+delegate_noop!(LayerShellApp: ignore ZwlrDataControlManagerV1);
 delegate_noop!(LayerShellApp: ignore ExtBackgroundEffectManagerV1);
 delegate_noop!(LayerShellApp: ignore ExtBackgroundEffectSurfaceV1);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn split(exec: &str) -> Option<Vec<String>> {
+        exec_arguments(exec, &ExecFields::default())
+    }
+
+    #[test]
+    fn quoted_arguments_keep_their_spaces() {
+        assert_eq!(split(r#"app "two words" "" last"#).unwrap(), ["app", "two words", "", "last"]);
+    }
+
+    #[test]
+    fn string_escapes_are_undone_before_quote_escapes() {
+        assert_eq!(split(r#"sh -c "echo \\$HOME \\"hi\\" \\\\""#).unwrap(), ["sh", "-c", r#"echo $HOME "hi" \"#]);
+    }
+
+    #[test]
+    fn escaped_spaces_split_unless_quoted() {
+        assert_eq!(split(r#"app "a\sb" c\sd"#).unwrap(), ["app", "a b", "c", "d"]);
+    }
+
+    #[test]
+    fn double_percent_is_a_literal_percent() {
+        assert_eq!(split("printf 100%%").unwrap(), ["printf", "100%"]);
+    }
+
+    #[test]
+    fn icon_code_expands_to_an_icon_flag() {
+        let fields = ExecFields {
+            icon: Some("firefox"),
+            ..ExecFields::default()
+        };
+        assert_eq!(exec_arguments("firefox %i %U", &fields).unwrap(), ["firefox", "--icon", "firefox"]);
+        assert_eq!(split("firefox %i %U").unwrap(), ["firefox"]);
+    }
+
+    #[test]
+    fn name_and_file_codes_expand_inside_words() {
+        let fields = ExecFields {
+            icon: None,
+            name: "Firefox",
+            file: "/usr/share/applications/firefox.desktop",
+        };
+        assert_eq!(
+            exec_arguments("app --name=%c %k", &fields).unwrap(),
+            ["app", "--name=Firefox", "/usr/share/applications/firefox.desktop"]
+        );
+    }
+
+    #[test]
+    fn file_and_url_codes_drop_their_whole_argument() {
+        assert_eq!(split("app --file=%f --new %u %F x%Uy").unwrap(), ["app", "--new"]);
+    }
+
+    #[test]
+    fn deprecated_codes_are_removed() {
+        assert_eq!(split("app %d%n x").unwrap(), ["app", "x"]);
+    }
+
+    #[test]
+    fn unterminated_quote_is_invalid() {
+        assert!(split(r#"app "open"#).is_none());
+        assert!(split(r#"app "open\"#).is_none());
+    }
+
+    fn player_stream(id: u32, volume: f64) -> Value {
+        serde_json::json!({
+            "id": id,
+            "info": {
+                "props": { "media.class": "Stream/Output/Audio", "application.name": "Spotify" },
+                "params": { "Props": [{ "channelVolumes": [volume, volume], "mute": false }] },
+            },
+        })
+    }
+
+    fn stream_node(change: Option<VolumeChange>) -> Option<Option<u32>> {
+        match change? {
+            VolumeChange::Player(stream) => Some(stream.map(|stream| stream.node)),
+            VolumeChange::Sink(_) => None,
+        }
+    }
+
+    #[test]
+    fn player_stream_stays_until_its_last_node_is_removed() {
+        let mut state = PipeWireState {
+            player: "spotify".to_owned(),
+            ..PipeWireState::default()
+        };
+        assert_eq!(stream_node(state.update(&player_stream(40, 1.0))), Some(Some(40)));
+        assert_eq!(stream_node(state.update(&player_stream(41, 0.125))), Some(Some(41)));
+        assert_eq!(stream_node(state.update(&serde_json::json!({ "id": 41, "info": null }))), Some(Some(40)));
+        assert_eq!(stream_node(state.update(&serde_json::json!({ "id": 7, "info": null }))), None);
+        assert_eq!(stream_node(state.update(&serde_json::json!({ "id": 40, "info": null }))), Some(None));
+    }
+}
@@ -1,15 +1,23 @@
 use crate::{
-    app::{CantusApp, config::Config, interaction::InteractionState, music::PlaybackState},
+    app::{
+        CantusApp,
+        config::{Config, LyricsAnnotation, Visualiser},
+        interaction::InteractionState,
+        music::PlaybackState,
+        timers::Timers,
+    },
     render::{
         FrameData, GAP, PANEL_START, RipplePulse,
+        karaoke::{EXTENSION as KARAOKE_EXTENSION, KaraokePass},
         launcher::{LauncherPass, LauncherState},
-        lyrics::{EXTENSION as LYRICS_EXTENSION, LyricsPass},
+        lyrics::{ANNOTATION_EXTENSION, EXTENSION as LYRICS_EXTENSION, LyricsPass},
         particles::ParticlePass,
         playhead::PlayheadPass,
         status::StatusPass,
         tempestas::{EXTENSION, TempestasPass},
         text::Renderer as TextRenderer,
         track::TrackPass,
+        visualiser::VisualiserPass,
     },
 };
 use isthmus::{
@@ -28,16 +36,20 @@ type RenderProgram = Program<FrameData, Systems>;
 pub struct Frame<'a> {
     pub shared: &'a mut FrameData,
     pub delta_time: f32,
+    /// Physical buffer pixels per logical pixel.
+    pub scale: f32,
     pub config: &'a Config,
     pub interaction: &'a mut InteractionState,
 }
 
 #[derive(isthmus::Render)]
 pub struct Systems {
+    pub karaoke: Option<KaraokePass>,
     pub lyrics: Option<LyricsPass>,
     pub tempestas: Option<TempestasPass>,
     pub status: Option<StatusPass>,
     pub track: TrackPass,
+    pub visualiser: Option<VisualiserPass>,
     pub particles: ParticlePass,
     pub playhead: PlayheadPass,
     pub launcher: LauncherPass,
@@ -57,6 +69,8 @@ pub struct RenderState {
     pub launcher_height: Option<f32>,
     /// The output's physical height, used as a fallback to size the launcher.
     pub output_height: Option<f32>,
+    /// Whether the karaoke panel showed last frame, the only time the bar surface makes room for it.
+    pub karaoke_open: bool,
 }
 
 pub fn approach(current: &mut f32, target: f32, speed: f32) {
@@ -64,7 +78,7 @@ pub fn approach(current: &mut f32, target: f32, speed: f32) {
 }
 
 impl<'a> Frame<'a> {
-    pub fn begin(shared: &'a mut FrameData, interaction: &'a mut InteractionState, config: &'a Config, elapsed: f32, screen_size: Vec2) -> Self {
+    pub fn begin(shared: &'a mut FrameData, interaction: &'a mut InteractionState, config: &'a Config, elapsed: f32, screen_size: Vec2, scale: f32) -> Self {
         let delta_time = (elapsed - shared.time).min(0.1);
         shared.time = elapsed;
         shared.screen_size = screen_size;
@@ -74,6 +88,7 @@ impl<'a> Frame<'a> {
         Self {
             shared,
             delta_time,
+            scale,
             config,
             interaction,
         }
@@ -105,6 +120,7 @@ impl Default for RenderState {
             launcher_width: None,
             launcher_height: None,
             output_height: None,
+            karaoke_open: false,
         }
     }
 }
@@ -122,34 +138,30 @@ impl RenderState {
 
 impl Systems {
     fn new(passes: &Passes<'_>, app: &CantusApp) -> Self {
-        let text = TextRenderer::new(passes);
-        let tempestas = app.config.tempestas_enabled.then(|| {
-            TempestasPass::new(
-                passes,
-                &text,
-                &app.config.timezones,
-                &app.enrichment.background,
-                app.updater.clone(),
-                app.enrichment.http.clone(),
-            )
-        });
+        let text = TextRenderer::new(passes, app.config.fallback_font.as_deref());
+        let tempestas = app
+            .config
+            .tempestas_enabled
+            .then(|| TempestasPass::new(passes, &text, &app.config, &app.enrichment.background, app.updater.clone(), app.enrichment.http.clone()));
         let status = app
             .config
             .status_enabled
-            .then(|| StatusPass::new(passes, &text, &app.enrichment.background, app.updater.clone()));
+            .then(|| StatusPass::new(passes, &text, &app.enrichment.background, app.updater.clone(), Arc::clone(&app.audio)));
         Self {
+            karaoke: app.config.lyrics_enabled.then(|| KaraokePass::new(passes)),
             lyrics: app.config.lyrics_enabled.then(|| LyricsPass::new(passes, &text, app.enrichment.clone(), app.music.clone())),
             tempestas,
             status,
-            track: TrackPass::new(passes, &text),
+            track: TrackPass::new(passes, &text, app.enrichment.clone()),
+            visualiser: (app.config.visualiser != Visualiser::Off).then(|| VisualiserPass::new(passes, Arc::clone(&app.audio), app.config.visualiser)),
             particles: ParticlePass::new(passes),
-            playhead: PlayheadPass::new(passes),
+            playhead: PlayheadPass::new(passes, app.enrichment.background.clone()),
             launcher: LauncherPass::new(passes, &text),
             text,
         }
     }
 
-    fn update(&mut self, frame: &mut Frame<'_>, playback: &mut PlaybackState, last_toggle_time: &mut f32, launcher: &mut LauncherState, launcher_size: Vec2) {
+    fn update(&mut self, frame: &mut Frame<'_>, playback: &mut PlaybackState, last_toggle_time: &mut f32, launcher: &mut LauncherState, timers: &Timers, launcher_size: Vec2) {
         self.text.begin();
         frame.shared.launcher_open = f32::from(launcher.open);
         let bar_size = frame.shared.screen_size;
@@ -168,25 +180,39 @@ impl Systems {
         };
         playback.update_timeline(drag_offset_ms, frame.interaction.dragging, frame.delta_time);
         if let Some(tempestas) = self.tempestas.as_mut() {
-            tempestas.update(&mut self.text, self.status.as_mut(), frame);
+            tempestas.update(&mut self.text, self.status.as_mut(), timers, frame);
         }
         if let Some(status) = self.status.as_mut() {
-            status.update(&mut self.text, frame);
+            status.update(&mut self.text, &mut playback.mixer, frame);
         }
         self.playhead.update(frame, playback, last_toggle_time);
         self.track.update(&mut self.text, playback, frame);
+        if let Some(visualiser) = self.visualiser.as_mut() {
+            visualiser.update(&self.track, frame);
+        }
+        let lyrics_shift = launcher.pending_lyrics_shift.take();
         if let Some(lyrics) = self.lyrics.as_mut() {
-            lyrics.update(&mut self.text, playback, frame);
+            lyrics.update(&mut self.text, self.karaoke.as_mut(), playback, lyrics_shift, frame);
         }
         self.particles.update(&self.track, playback, frame);
         self.text.upload();
     }
 
     fn draw_bar<'a>(&'a self, pass: &mut RenderPass<'a>) {
+        // Along the strip the visualiser sits behind the lyrics. The track pill is opaque, so a visualiser drawn before it
+        // would never show; instead it follows the track as a faint overlay clipped to the pill's capsule.
+        let strip = self.visualiser.as_ref().is_some_and(|visualiser| visualiser.placement == Visualiser::Strip);
+        self.karaoke.draw(pass);
+        if strip {
+            self.visualiser.draw(pass);
+        }
         self.lyrics.draw(pass);
         self.tempestas.draw(pass);
         self.status.draw(pass);
         self.track.draw(pass);
+        if !strip {
+            self.visualiser.draw(pass);
+        }
         self.particles.draw(pass);
         self.playhead.draw(pass);
     }
@@ -244,13 +270,15 @@ impl CantusApp {
 
     pub fn bar_surface_size(&self) -> (f32, f32) {
         let width = self.render.surface_width.unwrap_or(1920.0);
-        let extension = if self.config.tempestas_enabled {
-            EXTENSION
-        } else if self.config.lyrics_enabled {
-            LYRICS_EXTENSION
-        } else {
-            0.0
-        } + PANEL_OVERFLOW;
+        let tempestas = if self.config.tempestas_enabled { EXTENSION } else { 0.0 };
+        let lyrics = match (self.config.lyrics_enabled, self.render.karaoke_open) {
+            (true, true) => KARAOKE_EXTENSION,
+            (true, false) if self.config.lyrics_annotation == LyricsAnnotation::Off => LYRICS_EXTENSION,
+            (true, false) => LYRICS_EXTENSION + ANNOTATION_EXTENSION,
+            (false, _) => 0.0,
+        };
+        let visualiser = if self.config.visualiser == Visualiser::Strip { LYRICS_EXTENSION } else { 0.0 };
+        let extension = tempestas.max(lyrics).max(visualiser) + PANEL_OVERFLOW;
         (width, self.config.height + PANEL_START + extension)
     }
 
@@ -265,29 +293,40 @@ impl CantusApp {
     }
 
     pub fn render(&mut self) -> bool {
+        self.apply_launcher_timer();
         let (screen_width, screen_height) = self.bar_surface_size();
         let launcher_size = self.launcher_surface_size();
+        let playing = self.playback.playing.then(|| self.playback.queue.get(self.playback.timeline.index)).flatten();
+        self.audio.listen(playing.map(|track| track.uri.as_str()));
+        let (beat_phase, beat_strength) = self.audio.beat();
+        let beat_strength = if self.playback.playing { beat_strength } else { 0.0 };
         let Some(program) = self.render.program.as_mut() else {
             return false;
         };
-        let elapsed = self.render.start_time.elapsed().as_secs_f32();
+        let (elapsed, scale) = (self.render.start_time.elapsed().as_secs_f32(), self.render.scale);
+        let mut karaoke_open = false;
         let present = program.render_custom(
             Color::TRANSPARENT,
             |shared, systems| {
-                let mut frame = Frame::begin(shared, &mut self.interaction, &self.config, elapsed, vec2(screen_width, screen_height));
+                let mut frame = Frame::begin(shared, &mut self.interaction, &self.config, elapsed, vec2(screen_width, screen_height), scale);
+                frame.shared.beat_phase = beat_phase;
+                approach(&mut frame.shared.beat_strength, beat_strength, 2.0 * frame.delta_time);
                 systems.update(
                     &mut frame,
                     &mut self.playback,
                     &mut self.render.last_toggle_time,
                     &mut self.launcher,
+                    &self.timers,
                     vec2(launcher_size.0, launcher_size.1),
                 );
+                karaoke_open = systems.karaoke.as_ref().is_some_and(|karaoke| karaoke.panel.expansion > 0.0);
                 // The bar target must keep drawing while the launcher target is open.
                 frame.shared.launcher_open = 0.0;
                 frame.finish();
             },
             |systems, mut pass| systems.draw_bar(&mut pass),
         );
+        self.render.karaoke_open = karaoke_open;
         if self.launcher.open {
             program.update_shared(|shared| {
                 shared.screen_size = vec2(launcher_size.0, launcher_size.1);
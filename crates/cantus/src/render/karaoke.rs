@@ -0,0 +1,96 @@
+use crate::render::{
+    FrameData, GAP, PADDING, UNIT,
+    lyrics::EXTENSION as STRIP_EXTENSION,
+    shader::{fill, pixel_to_ndc, quad_coord, sd_rounded_box, sdf_coverage},
+};
+use isthmus::{
+    Vertex,
+    glam::{Vec2, Vec3, Vec4, vec2},
+    spirv_std::arch::kill,
+};
+
+#[cfg(feature = "cpu")]
+use crate::render::{cpu::Passes, text::TextStyle};
+
+/// Lines shown at once: the previous, current and next.
+pub const ROWS: usize = 3;
+pub const ROW_HEIGHT: f32 = UNIT * 7.0;
+/// Distance from the bottom of the bar to the top of the panel, clearing the lyrics strip.
+pub const TOP: f32 = STRIP_EXTENSION + UNIT * 5.0;
+pub const HEIGHT: f32 = PADDING * 2.0 + ROWS as f32 * ROW_HEIGHT;
+/// Surface space the expanded panel needs below the bar.
+pub const EXTENSION: f32 = TOP + HEIGHT;
+pub const MIN_WIDTH: f32 = 320.0;
+pub const FONT_SIZE: f32 = 17.0;
+#[cfg(feature = "cpu")]
+pub const STYLE: TextStyle = TextStyle::new(FONT_SIZE, 700.0);
+const RADIUS: f32 = 14.0;
+const SHADOW: f32 = 12.0;
+
+/// The backdrop of the expanded karaoke panel; its lines are drawn by the lyrics pass.
+#[isthmus::pass]
+pub struct KaraokePass {
+    pub panel: isthmus::Instance<Self>,
+}
+
+#[isthmus::data]
+#[derive(Default)]
+pub struct KaraokePanel {
+    pub origin: Vec2,
+    pub size: Vec2,
+    /// Eased reveal progress; the panel unrolls downwards from its top edge.
+    pub expansion: f32,
+    /// Row under the pointer, or -1.
+    pub hovered_row: i32,
+}
+
+#[derive(isthmus::Varyings)]
+pub struct Varyings {
+    pub pixel: Vec2,
+}
+
+#[isthmus::pass]
+impl KaraokePass {
+    pub fn new(passes: &Passes<'_>) -> Self {
+        Self {
+            panel: passes.instance(
+                (),
+                KaraokePanel {
+                    hovered_row: -1,
+                    ..KaraokePanel::default()
+                },
+            ),
+        }
+    }
+
+    #[gpu]
+    pub fn vertex(#[gpu(vertex_index)] vertex: u32, #[gpu(shared)] frame: FrameData, #[gpu(instance)] panel: KaraokePanel) -> Vertex<Varyings> {
+        let margin = Vec2::splat(SHADOW);
+        let pixel = panel.origin - margin + quad_coord(vertex) * (panel.size + margin * 2.0);
+        Vertex {
+            position: pixel_to_ndc(pixel, frame.screen_size),
+            varyings: Varyings { pixel },
+        }
+    }
+
+    #[gpu]
+    pub fn fragment(Varyings { pixel }: Varyings, #[gpu(shared)] frame: FrameData, #[gpu(instance)] panel: KaraokePanel) -> Vec4 {
+        if frame.launcher_open > 0.5 || panel.expansion <= 0.0 {
+            kill();
+        }
+        let size = vec2(panel.size.x, panel.size.y * panel.expansion);
+        let local = pixel - panel.origin;
+        let distance = sd_rounded_box(local - size * 0.5, size * 0.5, RADIUS.min(size.y * 0.5));
+        let mask = sdf_coverage(distance);
+        let shadow = (-distance.max(0.0) * 0.3).exp() * 0.16 * panel.expansion;
+
+        let row = ((local.y - PADDING) / ROW_HEIGHT).floor();
+        let row_center = vec2(size.x * 0.5, PADDING + (row + 0.5) * ROW_HEIGHT);
+        let hovered = if row as i32 == panel.hovered_row { 1.0 } else { 0.0 };
+        let highlight = fill(sd_rounded_box(local - row_center, vec2(size.x * 0.5 - GAP, ROW_HEIGHT * 0.5 - 2.0), 8.0)) * hovered;
+
+        let color = Vec3::splat(0.07).lerp(Vec3::splat(0.16), highlight);
+        let alpha = mask * 0.88 * panel.expansion;
+        (color * alpha).extend(alpha.max(shadow))
+    }
+}
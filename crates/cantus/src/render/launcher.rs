@@ -14,28 +14,45 @@ use {
     crate::{
         app::{
             Background,
-            config::SearchProvider,
+            config::{self, CommandProvider, Config, SearchProvider, Services},
+            http::{HttpClient, Response},
             interaction::Rect,
-            platform::{Current as Platform, DesktopApp, Platform as _},
+            platform::{Current as Platform, DesktopAction, DesktopApp, LaunchOptions, OpenWindow, Platform as _},
+            timers::{Timer, TimerKind},
             update,
         },
         render::{
             atlas::TextureAtlas,
             cpu::{Frame, Passes},
+            lyrics::LyricsShift,
             smoothstep,
             text::TextStyle,
         },
     },
     fend_core::Context,
     image::imageops::FilterType,
-    reqwest::{Client, Url},
+    jiff::Timestamp,
+    reqwest::Url,
     resvg::{
         render,
         tiny_skia::{Pixmap, Transform},
         usvg::{self, Tree},
     },
-    std::{collections::HashMap, error::Error, fs, ops::Range, path::Path, sync::OnceLock},
-    tokio::task::spawn_blocking,
+    serde::{Deserialize, Serialize},
+    std::{
+        cmp::Reverse,
+        collections::{HashMap, VecDeque},
+        error::Error,
+        fs, mem,
+        ops::Range,
+        path::{Path, PathBuf},
+        sync::{
+            Arc, OnceLock,
+            atomic::{AtomicU32, Ordering},
+        },
+        time::{Duration, SystemTime, UNIX_EPOCH},
+    },
+    tokio::{task::spawn_blocking, time::sleep},
     tracing::warn,
 };
 
@@ -43,13 +60,35 @@ const PANEL_WIDTH: f32 = 520.0;
 pub(crate) const BACKGROUND_RADIUS: i32 = 16;
 /// Matched-app/calculator rows shown below the search bar.
 pub(crate) const MAX_VISIBLE: usize = 8;
+/// Pause after the last keystroke before command providers run, so typing doesn't start one per character.
+#[cfg(feature = "cpu")]
+const COMMAND_DEBOUNCE: Duration = Duration::from_millis(150);
+#[cfg(feature = "cpu")]
+const HISTORY_FILE: &str = "cantus_launcher_history.json";
+#[cfg(feature = "cpu")]
+const CLIPBOARD_FILE: &str = "cantus_clipboard_history.json";
+/// Prefix which lists the clipboard history instead of searching.
+#[cfg(feature = "cpu")]
+const CLIPBOARD_ALIAS: &str = "!clip";
+/// Prefix which searches emoji and other characters by name.
+#[cfg(feature = "cpu")]
+const CHARACTER_ALIAS: &str = "!emoji";
+/// Named characters as `character<TAB>lowercase Unicode name<TAB>keywords`, emoji first; generated by `assets/characters.py`.
+#[cfg(feature = "cpu")]
+pub(crate) const CHARACTERS: &str = include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/../../assets/characters.tsv"));
+/// Launches unused for this many seconds are forgotten.
+#[cfg(feature = "cpu")]
+const HISTORY_LIFETIME: u64 = 365 * 86_400;
+/// Keeps an app's own row above its actions when both match equally.
+#[cfg(feature = "cpu")]
+const ACTION_PENALTY: i32 = 8;
 
 /// `LauncherRow::icon` sentinels; any other value indexes the icon texture array. `PANEL` marks
 /// the backdrop, which also draws the search field on itself.
 const PANEL: i32 = -3;
 const CALCULATOR_ICON: i32 = -2;
 #[cfg(feature = "cpu")]
-const SEARCH_ICON: i32 = -1;
+const NO_ICON: i32 = -1;
 
 /// Side of the square icon tile at the left of every row.
 const ICON_SIZE: f32 = 32.0;
@@ -65,6 +104,8 @@ mod host {
     pub const BADGE_WIDTHS: [f32; 2] = [27.0, 42.0];
     pub const ICON_PX: u32 = 48;
     pub const MAX_ICON_SLOTS: usize = 192;
+    /// Trailing icon layers for command rows, reused oldest first.
+    pub const COMMAND_ICON_SLOTS: usize = 16;
     pub const SEARCH_STYLE: TextStyle = TextStyle::new(18.0, 600.0);
     pub const NAME_STYLE: TextStyle = TextStyle::new(16.0, 700.0);
     pub const DETAIL_STYLE: TextStyle = TextStyle::new(13.0, 600.0);
@@ -73,7 +114,7 @@ mod host {
 }
 
 #[cfg(feature = "cpu")]
-use host::{BADGE_WIDTHS, DETAIL_COLOR, DETAIL_STYLE, ICON_PX, MAX_ICON_SLOTS, MUTED_COLOR, NAME_STYLE, SEARCH_STYLE};
+use host::{BADGE_WIDTHS, COMMAND_ICON_SLOTS, DETAIL_COLOR, DETAIL_STYLE, ICON_PX, MAX_ICON_SLOTS, MUTED_COLOR, NAME_STYLE, SEARCH_STYLE};
 
 /// Height of the search field, which sits flush at the top of the panel instead of in a pill.
 fn header_height(frame: &FrameData) -> f32 {
@@ -260,16 +301,41 @@ pub struct LauncherState {
     just_opened: bool,
     pub field: TextField,
     pub apps: Vec<DesktopApp>,
-    pub matches: Vec<u32>,
+    windows: Vec<OpenWindow>,
+    matches: Vec<Candidate>,
     /// The fend answer for the current query, if any.
     pub calc_result: Option<String>,
     /// Index of the highlighted entry, which enter and shift+enter act on.
     pub selected: usize,
     /// Text waiting to be put on the system clipboard by the platform layer.
     pub pending_copy: Option<String>,
+    /// Window waiting to be focused by the platform layer.
+    pub pending_focus: Option<u32>,
+    /// Lyric timing change waiting to be applied to the playing track by the lyrics pass.
+    pub pending_lyrics_shift: Option<LyricsShift>,
+    /// A timer to start, or to stop when it is running.
+    pub pending_timer: Option<(Timer, bool)>,
     pending_icons: Vec<(u32, Vec<u8>)>,
     providers: Vec<SearchEngine>,
+    commands: Vec<CommandProvider>,
+    launch: LaunchOptions,
+    /// Query the command rows were produced for.
+    command_query: String,
+    command_rows: Vec<CommandResult>,
+    /// Bumped whenever the command query changes, so stale runs are skipped or killed.
+    command_generation: Arc<AtomicU32>,
+    /// Icon path held by each command icon layer, filled afresh for every query.
+    command_icons: Vec<PathBuf>,
+    clipboard: ClipboardHistory,
+    /// Running timers, as last handed over by the app.
+    timers: Vec<Timer>,
+    /// Rows for the current `timer`, `alarm` or `stopwatch` command.
+    timer_rows: Vec<TimerRow>,
+    /// The change a `lyrics` command in the query spells out.
+    lyrics_shift: Option<LyricsShift>,
     calc: Context,
+    history: LaunchHistory,
+    background: Background,
 }
 
 #[cfg(feature = "cpu")]
@@ -279,25 +345,311 @@ struct SearchEngine {
     icon_layer: i32,
 }
 
+/// One row printed by a command provider.
+#[cfg(feature = "cpu")]
+#[derive(Deserialize)]
+struct CommandRow {
+    title: String,
+    #[serde(default)]
+    detail: String,
+    icon: Option<PathBuf>,
+    action: String,
+    alternate: Option<String>,
+    alternate_label: Option<String>,
+}
+
+#[cfg(feature = "cpu")]
+struct CommandResult {
+    provider: u32,
+    row: CommandRow,
+    icon_layer: i32,
+}
+
+/// Starts a timer a command spells out, or stops one that is running.
+#[cfg(feature = "cpu")]
+enum TimerRow {
+    Start(Timer),
+    Stop(Timer),
+}
+
+/// A ranked row, indexing open windows, apps, their actions, search providers, command rows, clipboard entries, named characters or timer rows,
+/// or the query's lyric timing change.
+#[cfg(feature = "cpu")]
+#[derive(Clone, Copy)]
+enum Candidate {
+    Window(u32),
+    App(u32),
+    Action(u32, u32),
+    Search(u32),
+    Command(u32),
+    Clip(u32),
+    Character(u32),
+    Timer(u32),
+    LyricsShift,
+}
+
 #[cfg(feature = "cpu")]
 enum LauncherEntry<'a> {
     Answer(&'a str),
+    Window(&'a OpenWindow, Option<&'a DesktopApp>),
     App(&'a DesktopApp),
+    Action(&'a DesktopApp, &'a DesktopAction),
     Search(&'a SearchEngine),
+    Command(&'a CommandResult),
+    Clip(&'a str),
+    Character(&'static NamedCharacter),
+    /// A timer, and whether it is already running.
+    Timer(&'a Timer, bool),
+    LyricsShift(LyricsShift),
+}
+
+/// How often and how recently each entry was launched, so frequently used entries rank higher.
+#[cfg(feature = "cpu")]
+#[derive(Default, Deserialize, Serialize)]
+struct LaunchHistory(HashMap<String, Launches>);
+
+#[cfg(feature = "cpu")]
+#[derive(Clone, Copy, Deserialize, Serialize)]
+struct Launches {
+    count: u32,
+    /// Unix seconds of the latest launch.
+    last: u64,
+}
+
+#[cfg(feature = "cpu")]
+impl LaunchHistory {
+    fn load() -> Self {
+        fs::read(config::directory().join(HISTORY_FILE))
+            .ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).inspect_err(|error| warn!(%error, "Failed to parse launcher history")).ok())
+            .unwrap_or_default()
+    }
+
+    /// Frecency bonus: launch count on a log scale, weighted by how recently the entry was used.
+    fn bonus(&self, key: &str, now: u64) -> i32 {
+        let Some(launches) = self.0.get(key) else {
+            return 0;
+        };
+        let recency = match now.saturating_sub(launches.last) / 86_400 {
+            0..4 => 40.0,
+            4..14 => 28.0,
+            14..31 => 20.0,
+            31..90 => 12.0,
+            _ => 4.0,
+        };
+        (recency * (launches.count as f32).ln_1p()) as i32
+    }
+
+    /// Counts a launch of `key`, forgets stale entries and saves the history in the background.
+    fn record(&mut self, key: String, now: u64, background: &Background) {
+        let launches = self.0.entry(key).or_insert(Launches { count: 0, last: now });
+        launches.count = launches.count.saturating_add(1);
+        launches.last = now;
+        self.0.retain(|_, launches| now.saturating_sub(launches.last) < HISTORY_LIFETIME);
+        let Ok(contents) = serde_json::to_vec(self) else {
+            return;
+        };
+        background.run(move || {
+            if let Err(error) = fs::write(config::directory().join(HISTORY_FILE), contents) {
+                warn!(%error, "Failed to save launcher history");
+            }
+        });
+    }
+}
+
+/// Scores `query` as a case-insensitive subsequence of `text`, ignoring its whitespace, or `None` when it isn't one.
+/// Characters that start a word or continue a run score highest, and every skipped character costs a little,
+/// so `ff` finds Firefox and `vsc` prefers Visual Studio Code.
+#[cfg(feature = "cpu")]
+fn fuzzy_score(query: &str, text: &str) -> Option<i32> {
+    const MATCH: i32 = 16;
+    const WORD_START: i32 = 24;
+    const CONSECUTIVE: i32 = 20;
+    const GAP: i32 = 1;
+
+    let query = query.chars().filter(|c| !c.is_whitespace()).flat_map(char::to_lowercase).collect::<Vec<_>>();
+    if query.is_empty() {
+        return Some(0);
+    }
+    let chars = text.chars().collect::<Vec<_>>();
+    let lower = chars.iter().map(|&c| c.to_lowercase().next().unwrap_or(c)).collect::<Vec<_>>();
+    let bonus = (0..chars.len())
+        .map(|index| match index.checked_sub(1).map(|previous| chars[previous]) {
+            None => WORD_START,
+            Some(previous) if !previous.is_alphanumeric() || (previous.is_lowercase() && chars[index].is_uppercase()) => WORD_START,
+            Some(_) => 0,
+        })
+        .collect::<Vec<_>>();
+
+    // Best score of the query so far with its last character matched at each position of `text`.
+    let mut previous = vec![None::<i32>; chars.len()];
+    let mut current = previous.clone();
+    for (query_index, &wanted) in query.iter().enumerate() {
+        // Best earlier score, offset so subtracting the current position charges the gap since it.
+        let mut best_before = None::<i32>;
+        for index in 0..chars.len() {
+            let position = index as i32;
+            current[index] = (lower[index] == wanted)
+                .then(|| {
+                    if query_index == 0 {
+                        return Some(-GAP * position);
+                    }
+                    let gapped = best_before.map(|best| best - GAP * (position - 1));
+                    let run = index.checked_sub(1).and_then(|before| previous[before]).map(|score| score + CONSECUTIVE);
+                    gapped.max(run)
+                })
+                .flatten()
+                .map(|score| score + MATCH + bonus[index]);
+            best_before = best_before.max(previous[index].map(|score| score + GAP * position));
+        }
+        mem::swap(&mut previous, &mut current);
+    }
+    previous.into_iter().flatten().max()
+}
+
+/// The rest of `query` when it starts with `alias` as a whole word.
+#[cfg(feature = "cpu")]
+fn strip_alias<'a>(query: &'a str, alias: &str) -> Option<&'a str> {
+    let rest = query.strip_prefix(alias)?;
+    (rest.is_empty() || rest.starts_with(char::is_whitespace)).then(|| rest.trim())
+}
+
+/// A character listed by the `!emoji` mode.
+#[cfg(feature = "cpu")]
+struct NamedCharacter {
+    character: char,
+    name: &'static str,
+    keywords: &'static str,
+}
+
+#[cfg(feature = "cpu")]
+fn named_characters() -> &'static [NamedCharacter] {
+    static TABLE: OnceLock<Vec<NamedCharacter>> = OnceLock::new();
+    TABLE.get_or_init(|| {
+        CHARACTERS
+            .lines()
+            .filter_map(|line| {
+                let mut fields = line.split('\t');
+                Some(NamedCharacter {
+                    character: fields.next()?.chars().next()?,
+                    name: fields.next()?,
+                    keywords: fields.next().unwrap_or_default(),
+                })
+            })
+            .collect()
+    })
+}
+
+/// Characters whose name or keywords have a word starting with each word of `filter`; exact
+/// names first, then name matches, then keyword matches, each in table order.
+#[cfg(feature = "cpu")]
+fn character_matches(filter: &str) -> Vec<Candidate> {
+    let filter = filter.to_lowercase();
+    let words = filter.split_whitespace().collect::<Vec<_>>();
+    let covers = |text: &str| words.iter().all(|word| text.split([' ', '-']).any(|candidate| candidate.starts_with(word)));
+    let mut ranked = named_characters()
+        .iter()
+        .enumerate()
+        .filter_map(|(index, entry)| {
+            let rank = if entry.name == filter {
+                0
+            } else if covers(entry.name) {
+                1
+            } else if covers(&format!("{} {}", entry.name, entry.keywords)) {
+                2
+            } else {
+                return None;
+            };
+            Some((rank, Candidate::Character(index as u32)))
+        })
+        .collect::<Vec<_>>();
+    ranked.sort_by_key(|&(rank, _)| rank);
+    ranked.into_iter().take(MAX_VISIBLE).map(|(_, candidate)| candidate).collect()
+}
+
+/// Recently copied texts, newest first, listed by the `!clip` mode.
+#[cfg(feature = "cpu")]
+struct ClipboardHistory {
+    entries: VecDeque<String>,
+    limit: usize,
+    persist: bool,
+}
+
+#[cfg(feature = "cpu")]
+impl ClipboardHistory {
+    fn load(config: &Config) -> Self {
+        let entries = config
+            .clipboard_history_persist
+            .then(|| fs::read(config::directory().join(CLIPBOARD_FILE)).ok())
+            .flatten()
+            .and_then(|bytes| serde_json::from_slice(&bytes).inspect_err(|error| warn!(%error, "Failed to parse clipboard history")).ok())
+            .unwrap_or_default();
+        Self {
+            entries,
+            limit: config.clipboard_history as usize,
+            persist: config.clipboard_history_persist,
+        }
+    }
+
+    /// Moves `text` to the front, drops the oldest past the limit and saves the history in the background when persistent.
+    fn record(&mut self, text: String, background: &Background) {
+        if text.trim().is_empty() || self.limit == 0 {
+            return;
+        }
+        self.entries.retain(|entry| *entry != text);
+        self.entries.push_front(text);
+        self.entries.truncate(self.limit);
+        if !self.persist {
+            return;
+        }
+        let Ok(contents) = serde_json::to_vec(&self.entries) else {
+            return;
+        };
+        background.run(move || {
+            if let Err(error) = fs::write(config::directory().join(CLIPBOARD_FILE), contents) {
+                warn!(%error, "Failed to save clipboard history");
+            }
+        });
+    }
+}
+
+/// Rows for a `timer`, `alarm` or `stopwatch` command: starting the one it spells out, then stopping those running.
+#[cfg(feature = "cpu")]
+fn timer_rows(query: &str, active: &[Timer]) -> Vec<TimerRow> {
+    let Some((kind, argument)) = Timer::command(query) else {
+        return Vec::new();
+    };
+    // A bare keyword or `timer cancel` lists what's running; a bare `stopwatch` also offers to start one.
+    let listing = argument.is_empty() || matches!(argument.to_ascii_lowercase().as_str(), "cancel" | "stop");
+    Timer::parse(kind, argument)
+        .map(TimerRow::Start)
+        .into_iter()
+        .chain(active.iter().filter(|_| listing).cloned().map(TimerRow::Stop))
+        .take(MAX_VISIBLE)
+        .collect()
+}
+
+#[cfg(feature = "cpu")]
+fn unix_now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs()
 }
 
 #[cfg(feature = "cpu")]
 impl LauncherState {
-    pub(crate) fn new(background: &Background, http: &Client, providers: impl IntoIterator<Item = SearchProvider>) -> Self {
+    pub(crate) fn new(background: &Background, http: &HttpClient, config: &Config) -> Self {
         let mut calc = Context::new();
-        fetch_exchange_rates(background, http.clone());
-        calc.set_exchange_rate_handler_v2(ExchangeRates);
-        let providers = providers
-            .into_iter()
-            .map(|config| SearchEngine {
-                label: format!("Search with {}", config.name),
-                config,
-                icon_layer: SEARCH_ICON,
+        if let Some(base) = Services::endpoint(&config.services.exchange_rates) {
+            fetch_exchange_rates(background, http.clone(), format!("{base}/latest/USD"));
+            calc.set_exchange_rate_handler_v2(ExchangeRates);
+        }
+        let providers = config
+            .search_providers
+            .iter()
+            .cloned()
+            .map(|provider| SearchEngine {
+                label: format!("Search with {}", provider.name),
+                config: provider,
+                icon_layer: NO_ICON,
             })
             .collect::<Vec<_>>();
         start_scan(background, http, &providers);
@@ -306,13 +658,32 @@ impl LauncherState {
             just_opened: false,
             field: TextField::default(),
             apps: Vec::new(),
+            windows: Vec::new(),
             matches: Vec::new(),
             calc_result: None,
             selected: 0,
             pending_copy: None,
+            pending_focus: None,
+            pending_lyrics_shift: None,
+            pending_timer: None,
             pending_icons: Vec::new(),
             providers,
+            commands: config.launcher_commands.clone(),
+            launch: LaunchOptions {
+                terminal: config.terminal.clone().or_else(Platform::terminal_template),
+                systemd_scopes: config.systemd_scopes,
+            },
+            command_query: String::new(),
+            command_rows: Vec::new(),
+            command_generation: Arc::default(),
+            command_icons: Vec::new(),
+            clipboard: ClipboardHistory::load(config),
+            timers: Vec::new(),
+            timer_rows: Vec::new(),
+            lyrics_shift: None,
             calc,
+            history: LaunchHistory::load(),
+            background: background.clone(),
         }
     }
 
@@ -360,6 +731,47 @@ impl LauncherState {
     }
 
     pub fn refresh_matches(&mut self) {
+        // The clipboard, character, timer and lyrics modes list only their own entries, except that a bare
+        // `stopwatch` may also be the start of an app's name, so its rows go above the usual results instead.
+        let text = self.field.text.trim();
+        self.timer_rows = timer_rows(text, &self.timers);
+        let stopwatch_rows = if matches!(Timer::command(text), Some((TimerKind::Stopwatch, ""))) {
+            self.timer_rows.len()
+        } else {
+            0
+        };
+        self.lyrics_shift = LyricsShift::command(text);
+        let listed = if let Some(filter) = strip_alias(text, CLIPBOARD_ALIAS) {
+            // Newest first; the filter only narrows the list, so recency stays the order.
+            let filter = filter.to_lowercase();
+            Some(
+                self.clipboard
+                    .entries
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, text)| text.to_lowercase().contains(&filter))
+                    .map(|(index, _)| Candidate::Clip(index as u32))
+                    .take(MAX_VISIBLE)
+                    .collect(),
+            )
+        } else {
+            strip_alias(text, CHARACTER_ALIAS)
+                .map(character_matches)
+                .or_else(|| (stopwatch_rows == 0 && !self.timer_rows.is_empty()).then(|| (0..self.timer_rows.len() as u32).map(Candidate::Timer).collect()))
+                .or_else(|| self.lyrics_shift.map(|_| vec![Candidate::LyricsShift]))
+        };
+        if let Some(matches) = listed {
+            self.matches = matches;
+            self.calc_result = None;
+            if !self.command_query.is_empty() {
+                self.command_query.clear();
+                self.command_rows.clear();
+                self.command_icons.clear();
+                self.command_generation.fetch_add(1, Ordering::Relaxed);
+            }
+            self.selected = 0;
+            return;
+        }
         let (provider, query) = self.search_query();
         let explicit_search = provider.is_some();
         let query = query.to_owned();
@@ -368,27 +780,196 @@ impl LauncherState {
             .flatten()
             .map(|result| result.get_main_result().to_owned())
             .filter(|result| !result.is_empty() && result != &query);
+        let command_query = if explicit_search { "" } else { query.as_str() };
+        if self.command_query != command_query {
+            self.command_query = command_query.to_owned();
+            self.command_rows.clear();
+            self.command_icons.clear();
+            self.run_commands();
+        }
 
-        let query = query.to_lowercase();
-        let has_search = !self.providers.is_empty() && (!query.is_empty() || explicit_search);
-        let visible = MAX_VISIBLE - usize::from(self.calc_result.is_some()) - usize::from(has_search);
-        let mut scored = self
-            .apps
-            .iter()
-            .enumerate()
-            .filter(|_| !explicit_search)
-            .filter_map(|(index, app)| {
-                let name = app.name.to_lowercase();
-                name.contains(&query).then(|| (index as u32, name.starts_with(&query)))
-            })
-            .collect::<Vec<_>>();
-        scored.sort_by_key(|&(_, prefix_match)| !prefix_match);
-        self.matches = scored.into_iter().take(visible).map(|(index, _)| index).collect();
+        // Windows, apps, their actions, search providers and command rows compete on one fuzzy score, lifted by frecency.
+        let now = unix_now();
+        let visible = MAX_VISIBLE.saturating_sub(usize::from(self.calc_result.is_some()) + stopwatch_rows);
+        let mut scored = Vec::new();
+        if let Some(index) = provider {
+            scored.push((0, Candidate::Search(index as u32)));
+        }
+        // Windows come before apps, so switching wins a tie with launching another instance. They only show for a query,
+        // so the idle list stays the most used apps however many windows are open.
+        for (index, window) in self.windows.iter().enumerate().filter(|_| !explicit_search && !query.is_empty()) {
+            let candidate = Candidate::Window(index as u32);
+            let app_name = self.window_app(window).map_or(window.app_id.as_str(), |app| app.name.as_str());
+            if let Some(score) = fuzzy_score(&query, &window.title).max(fuzzy_score(&query, app_name)) {
+                scored.push((score + self.history.bonus(&self.candidate_key(candidate), now), candidate));
+            }
+        }
+        for (index, app) in self.apps.iter().enumerate().filter(|_| !explicit_search) {
+            let index = index as u32;
+            if let Some(score) = fuzzy_score(&query, &app.name) {
+                scored.push((score + self.history.bonus(&self.candidate_key(Candidate::App(index)), now), Candidate::App(index)));
+            }
+            // Actions only show for a query, so the idle list stays one row per app.
+            for (action, DesktopAction { name, .. }) in app.actions.iter().enumerate().filter(|_| !query.is_empty()) {
+                let candidate = Candidate::Action(index, action as u32);
+                if let Some(score) = fuzzy_score(&query, &format!("{} {name}", app.name)) {
+                    scored.push((score - ACTION_PENALTY + self.history.bonus(&self.candidate_key(candidate), now), candidate));
+                }
+            }
+        }
+        // Commands filter rows themselves, so rows the query doesn't spell out still show, after those it does.
+        for (index, result) in self.command_rows.iter().enumerate() {
+            let candidate = Candidate::Command(index as u32);
+            let score = fuzzy_score(&query, &result.row.title).unwrap_or(0);
+            scored.push((score + self.history.bonus(&self.candidate_key(candidate), now), candidate));
+        }
+        let fallback = !explicit_search && !query.is_empty() && !self.providers.is_empty();
+        for (index, engine) in self.providers.iter().enumerate().filter(|_| fallback) {
+            let candidate = Candidate::Search(index as u32);
+            if let Some(score) = fuzzy_score(&query, &engine.config.name) {
+                scored.push((score + self.history.bonus(&self.candidate_key(candidate), now), candidate));
+            }
+        }
+        // Stable, so equal scores keep the apps' alphabetical order.
+        scored.sort_by_key(|&(score, _)| Reverse(score));
+        self.matches = scored.into_iter().take(visible).map(|(_, candidate)| candidate).collect();
+        // The first provider always offers to search the query, after every match unless it ranked among them.
+        if fallback && visible > 0 && !self.matches.iter().any(|candidate| matches!(candidate, Candidate::Search(0))) {
+            self.matches.truncate(visible - 1);
+            self.matches.push(Candidate::Search(0));
+        }
+        self.matches.splice(0..0, (0..stopwatch_rows as u32).map(Candidate::Timer));
         self.selected = 0;
     }
 
+    /// Replaces the open windows offered to switch to, keeping the highlight where it is.
+    pub fn set_windows(&mut self, windows: Vec<OpenWindow>) {
+        self.windows = windows;
+        if self.open {
+            let selected = self.selected;
+            self.refresh_matches();
+            self.selected = selected.min(self.entry_count().saturating_sub(1));
+        } else {
+            // Opening re-runs the query, so stale window indices must not linger until then.
+            self.matches.clear();
+        }
+    }
+
+    /// Replaces the running timers offered to stop, keeping the highlight where it is.
+    pub fn set_timers(&mut self, timers: &[Timer]) {
+        self.timers = timers.to_vec();
+        if self.open {
+            let selected = self.selected;
+            self.refresh_matches();
+            self.selected = selected.min(self.entry_count().saturating_sub(1));
+        }
+    }
+
+    /// The desktop app `window` belongs to, matched on its app id.
+    fn window_app(&self, window: &OpenWindow) -> Option<&DesktopApp> {
+        let app_id = window.app_id.as_str();
+        self.apps.iter().find(|app| {
+            app.id.eq_ignore_ascii_case(app_id)
+                || app.id.rsplit('.').next().is_some_and(|name| name.eq_ignore_ascii_case(app_id))
+                || app.wm_class.as_ref().is_some_and(|class| class.eq_ignore_ascii_case(app_id))
+        })
+    }
+
+    /// Puts copied `text` at the front of the clipboard history.
+    pub fn record_clipboard(&mut self, text: String) {
+        self.clipboard.record(text, &self.background);
+        if self.open && strip_alias(self.field.text.trim(), CLIPBOARD_ALIAS).is_some() {
+            self.refresh_matches();
+        }
+    }
+
+    /// Names `candidate` in the launch history.
+    fn candidate_key(&self, candidate: Candidate) -> String {
+        match candidate {
+            Candidate::Window(window) => format!("window:{}", self.windows[window as usize].app_id),
+            Candidate::App(app) => format!("app:{}", self.apps[app as usize].name),
+            Candidate::Action(app, action) => {
+                let app = &self.apps[app as usize];
+                format!("action:{}:{}", app.name, app.actions[action as usize].name)
+            }
+            Candidate::Search(engine) => format!("search:{}", self.providers[engine as usize].config.alias),
+            Candidate::Command(row) => {
+                let result = &self.command_rows[row as usize];
+                format!("command:{}:{}", self.commands[result.provider as usize].name, result.row.title)
+            }
+            Candidate::Clip(_) | Candidate::Character(_) | Candidate::Timer(_) | Candidate::LyricsShift => {
+                unreachable!("clipboard entries, characters, timers and lyric shifts stay out of the launch history")
+            }
+        }
+    }
+
+    /// Runs every command provider on the current query in the background once typing pauses,
+    /// killing any still running for an earlier query.
+    fn run_commands(&self) {
+        let generation = self.command_generation.fetch_add(1, Ordering::Relaxed) + 1;
+        if self.command_query.is_empty() {
+            return;
+        }
+        for (index, provider) in self.commands.iter().enumerate() {
+            let (name, command, query) = (provider.name.clone(), provider.command.clone(), self.command_query.clone());
+            let timeout = Duration::from_millis(provider.timeout_ms.into());
+            let current = Arc::clone(&self.command_generation);
+            self.background.spawn(async move {
+                let stale = move || current.load(Ordering::Relaxed) != generation;
+                sleep(COMMAND_DEBOUNCE).await;
+                if stale() {
+                    return None;
+                }
+                let (query, rows) = spawn_blocking(move || {
+                    let output = Platform::command_output(&command, &query, timeout, stale)?;
+                    Some((query, parse_command_rows(&name, &output)))
+                })
+                .await
+                .ok()??;
+                Some(update(move |app| app.launcher.receive_command_rows(index, &query, rows)))
+            });
+        }
+    }
+
+    /// Adds a command's rows, unless the query has changed since it ran, keeping the highlight where it is.
+    fn receive_command_rows(&mut self, provider: usize, query: &str, rows: Vec<(CommandRow, Option<Vec<u8>>)>) {
+        if query != self.command_query {
+            return;
+        }
+        for (row, pixels) in rows {
+            let icon_layer = match (row.icon.as_deref(), pixels) {
+                (Some(path), Some(pixels)) => self.command_icon_layer(path, pixels),
+                _ => NO_ICON,
+            };
+            self.command_rows.push(CommandResult {
+                provider: provider as u32,
+                row,
+                icon_layer,
+            });
+        }
+        let selected = self.selected;
+        self.refresh_matches();
+        self.selected = selected.min(self.entry_count().saturating_sub(1));
+    }
+
+    /// Texture layer showing the icon at `path`, uploading `pixels` into a free command slot when it isn't loaded,
+    /// or [`NO_ICON`] once this query's rows have used every slot.
+    fn command_icon_layer(&mut self, path: &Path, pixels: Vec<u8>) -> i32 {
+        let slot = match self.command_icons.iter().position(|loaded| loaded == path) {
+            Some(slot) => slot,
+            None if self.command_icons.len() < COMMAND_ICON_SLOTS => {
+                self.command_icons.push(path.to_owned());
+                let slot = self.command_icons.len() - 1;
+                self.pending_icons.push(((MAX_ICON_SLOTS - COMMAND_ICON_SLOTS + slot) as u32, pixels));
+                slot
+            }
+            None => return NO_ICON,
+        };
+        (MAX_ICON_SLOTS - COMMAND_ICON_SLOTS + slot) as i32
+    }
+
     pub fn entry_count(&self) -> usize {
-        usize::from(self.calc_result.is_some()) + self.matches.len() + usize::from(self.search_provider().is_some())
+        usize::from(self.calc_result.is_some()) + self.matches.len()
     }
 
     fn entry(&self, row: usize) -> Option<LauncherEntry<'_>> {
@@ -399,11 +980,26 @@ impl LauncherState {
             }
             row -= 1;
         }
-        self.matches
-            .get(row)
-            .and_then(|&app| self.apps.get(app as usize))
-            .map(LauncherEntry::App)
-            .or_else(|| (row == self.matches.len()).then(|| self.search_provider()).flatten().map(LauncherEntry::Search))
+        Some(match *self.matches.get(row)? {
+            Candidate::Window(window) => {
+                let window = &self.windows[window as usize];
+                LauncherEntry::Window(window, self.window_app(window))
+            }
+            Candidate::App(app) => LauncherEntry::App(&self.apps[app as usize]),
+            Candidate::Action(app, action) => {
+                let app = &self.apps[app as usize];
+                LauncherEntry::Action(app, &app.actions[action as usize])
+            }
+            Candidate::Search(engine) => LauncherEntry::Search(&self.providers[engine as usize]),
+            Candidate::Command(row) => LauncherEntry::Command(&self.command_rows[row as usize]),
+            Candidate::Clip(entry) => LauncherEntry::Clip(&self.clipboard.entries[entry as usize]),
+            Candidate::Character(entry) => LauncherEntry::Character(&named_characters()[entry as usize]),
+            Candidate::Timer(row) => match &self.timer_rows[row as usize] {
+                TimerRow::Start(timer) => LauncherEntry::Timer(timer, false),
+                TimerRow::Stop(timer) => LauncherEntry::Timer(timer, true),
+            },
+            Candidate::LyricsShift => LauncherEntry::LyricsShift(self.lyrics_shift?),
+        })
     }
 
     /// Moves the highlight by `delta` rows, stopping at either end.
@@ -413,9 +1009,29 @@ impl LauncherState {
 
     /// Runs row `index`'s action — its alternative one when `alternate` is set — then dismisses.
     pub fn activate(&mut self, index: usize, alternate: bool) {
+        let candidate = index
+            .checked_sub(usize::from(self.calc_result.is_some()))
+            .and_then(|row| self.matches.get(row).copied())
+            .filter(|candidate| !matches!(candidate, Candidate::Clip(_) | Candidate::Character(_) | Candidate::Timer(_) | Candidate::LyricsShift))
+            .map(|candidate| match candidate {
+                Candidate::App(app) if alternate && !self.apps[app as usize].actions.is_empty() => Candidate::Action(app, 0),
+                candidate => candidate,
+            });
         match self.entry(index) {
-            Some(LauncherEntry::App(app)) => Platform::spawn(app.action.as_ref().filter(|_| alternate).map_or(&app.exec, |(_, exec)| exec)),
-            Some(LauncherEntry::Answer(answer)) => self.pending_copy = Some(answer.to_owned()),
+            Some(LauncherEntry::App(app)) => Platform::launch(&self.background, app, app.actions.first().filter(|_| alternate), &self.launch),
+            Some(LauncherEntry::Action(app, action)) => Platform::launch(&self.background, app, Some(action), &self.launch),
+            Some(LauncherEntry::Command(CommandResult { row, .. })) => Platform::run_command(row.alternate.as_ref().filter(|_| alternate).unwrap_or(&row.action)),
+            Some(LauncherEntry::Window(window, _)) => self.pending_focus = Some(window.id),
+            Some(LauncherEntry::Answer(text) | LauncherEntry::Clip(text)) => self.pending_copy = Some(text.to_owned()),
+            Some(LauncherEntry::Character(entry)) => {
+                self.pending_copy = Some(if alternate {
+                    format!("U+{:04X}", u32::from(entry.character))
+                } else {
+                    entry.character.to_string()
+                });
+            }
+            Some(LauncherEntry::Timer(timer, running)) => self.pending_timer = Some((timer.clone(), running)),
+            Some(LauncherEntry::LyricsShift(shift)) => self.pending_lyrics_shift = Some(shift),
             Some(LauncherEntry::Search(engine)) => {
                 let terms = self.search_query().1;
                 let encoded = form_urlencoded::byte_serialize(terms.as_bytes()).collect::<String>();
@@ -423,6 +1039,10 @@ impl LauncherState {
             }
             None => return,
         }
+        if let Some(candidate) = candidate {
+            let key = self.candidate_key(candidate);
+            self.history.record(key, unix_now(), &self.background);
+        }
         self.open = false;
         self.just_opened = false;
         self.field.clear();
@@ -433,13 +1053,9 @@ impl LauncherState {
         let query = self.field.text.trim();
         self.providers
             .iter()
-            .position(|provider| query == provider.config.alias || query.strip_prefix(&provider.config.alias).is_some_and(|rest| rest.starts_with(char::is_whitespace)))
-            .map_or((None, query), |index| (Some(index), query[self.providers[index].config.alias.len()..].trim()))
-    }
-
-    fn search_provider(&self) -> Option<&SearchEngine> {
-        let (provider, query) = self.search_query();
-        (!query.is_empty() || provider.is_some()).then(|| self.providers.get(provider.unwrap_or(0))).flatten()
+            .enumerate()
+            .find_map(|(index, provider)| Some((Some(index), strip_alias(query, &provider.config.alias)?)))
+            .unwrap_or((None, query))
     }
 }
 
@@ -462,10 +1078,10 @@ impl fend_core::ExchangeRateFnV2 for ExchangeRates {
 }
 
 #[cfg(feature = "cpu")]
-fn fetch_exchange_rates(background: &Background, http: Client) {
+fn fetch_exchange_rates(background: &Background, http: HttpClient, url: String) {
     background.spawn(async move {
-        if let Ok(response) = http.get("https://open.er-api.com/v6/latest/USD").send().await.and_then(reqwest::Response::error_for_status)
-            && let Ok(body) = response.json::<CurrencyRates>().await
+        if let Ok(response) = http.get(url).send().await.and_then(Response::error_for_status)
+            && let Ok(body) = response.json::<CurrencyRates>()
         {
             let _ = EXCHANGE_RATES.set(body.rates);
         }
@@ -597,16 +1213,65 @@ impl LauncherPass {
             if interactive && frame.interaction.surface(row).clicked {
                 activated = Some(index);
             }
+            let (formatted, named);
             let (icon, name, detail, action, alternate) = match launcher.entry(index).unwrap() {
                 LauncherEntry::App(app) => (
                     app.icon_layer,
                     app.name.as_str(),
                     app.comment.as_str(),
                     "Open",
-                    app.action.as_ref().map(|(label, _)| label.as_str()),
+                    app.actions.first().map(|action| action.name.as_str()),
+                ),
+                LauncherEntry::Window(window, app) => (
+                    app.map_or(NO_ICON, |app| app.icon_layer),
+                    window.title.as_str(),
+                    app.map_or(window.app_id.as_str(), |app| app.name.as_str()),
+                    "Switch",
+                    None,
                 ),
+                LauncherEntry::Action(app, action) => (app.icon_layer, action.name.as_str(), app.name.as_str(), "Open", None),
                 LauncherEntry::Answer(answer) => (CALCULATOR_ICON, answer, "", "Copy", None),
+                LauncherEntry::Clip(text) => {
+                    let mut lines = text.lines().map(str::trim).filter(|line| !line.is_empty());
+                    let first = lines.next().unwrap_or_default();
+                    formatted = match lines.count() {
+                        0 => String::new(),
+                        more => format!("+{more} more lines"),
+                    };
+                    (NO_ICON, first, formatted.as_str(), "Copy", None)
+                }
+                LauncherEntry::Character(entry) => {
+                    formatted = format!("{}  {}", entry.character, entry.name);
+                    (NO_ICON, formatted.as_str(), entry.keywords, "Copy", Some("Code"))
+                }
+                LauncherEntry::Timer(timer, running) => {
+                    let mut reading = String::from(match timer.kind {
+                        TimerKind::Countdown => "Ends in ",
+                        TimerKind::Alarm => "Rings in ",
+                        TimerKind::Stopwatch => "Elapsed ",
+                    });
+                    timer.write_remaining(Timestamp::now(), &mut reading).unwrap();
+                    formatted = reading;
+                    named = timer.name();
+                    let action = match (running, timer.kind) {
+                        (false, _) => "Start",
+                        (true, TimerKind::Stopwatch) => "Stop",
+                        (true, _) => "Cancel",
+                    };
+                    (NO_ICON, named.as_str(), formatted.as_str(), action, None)
+                }
+                LauncherEntry::LyricsShift(shift) => {
+                    named = shift.describe();
+                    (NO_ICON, named.as_str(), "For the playing track", "Apply", None)
+                }
                 LauncherEntry::Search(engine) => (engine.icon_layer, engine.label.as_str(), launcher.search_query().1, "Search", None),
+                LauncherEntry::Command(CommandResult { row, icon_layer, .. }) => (
+                    *icon_layer,
+                    row.title.as_str(),
+                    row.detail.as_str(),
+                    "Run",
+                    row.alternate.as_ref().map(|_| row.alternate_label.as_deref().unwrap_or("Alternate")),
+                ),
             };
 
             // Only the highlighted row spells out what enter and shift+enter would do.
@@ -751,8 +1416,8 @@ impl LauncherPass {
 
 /// Scans installed apps and decodes their icons on a background thread, then applies the result.
 #[cfg(feature = "cpu")]
-fn start_scan(background: &Background, http: &Client, providers: &[SearchEngine]) {
-    let provider_icon_count = providers.len().min(MAX_ICON_SLOTS);
+fn start_scan(background: &Background, http: &HttpClient, providers: &[SearchEngine]) {
+    let provider_icon_count = providers.len().min(MAX_ICON_SLOTS - COMMAND_ICON_SLOTS);
     let provider_icons = providers
         .iter()
         .take(provider_icon_count)
@@ -762,7 +1427,7 @@ fn start_scan(background: &Background, http: &Client, providers: &[SearchEngine]
             Some((index, url))
         })
         .collect::<Vec<_>>();
-    let app_slots = MAX_ICON_SLOTS - provider_icon_count;
+    let app_slots = MAX_ICON_SLOTS - COMMAND_ICON_SLOTS - provider_icon_count;
     background.spawn(async move {
         let (apps, icon_writes) = spawn_blocking(move || {
             let mut apps = Platform::desktop_apps();
@@ -804,9 +1469,30 @@ fn start_scan(background: &Background, http: &Client, providers: &[SearchEngine]
     }
 }
 
+/// Reads a command's rows, as a JSON array or one object per line, with the icons they name.
+#[cfg(feature = "cpu")]
+fn parse_command_rows(provider: &str, output: &[u8]) -> Vec<(CommandRow, Option<Vec<u8>>)> {
+    let rows = serde_json::from_slice::<Vec<CommandRow>>(output).or_else(|_| {
+        output
+            .split(|&byte| byte == b'\n')
+            .filter(|line| !line.trim_ascii().is_empty())
+            .map(serde_json::from_slice)
+            .collect::<Result<Vec<_>, _>>()
+    });
+    rows.inspect_err(|error| warn!(%error, provider, "Failed to parse command rows"))
+        .unwrap_or_default()
+        .into_iter()
+        .take(MAX_VISIBLE)
+        .map(|row| {
+            let pixels = row.icon.as_deref().and_then(load_icon_pixels);
+            (row, pixels)
+        })
+        .collect()
+}
+
 #[cfg(feature = "cpu")]
-async fn fetch_favicon(http: &Client, page: Url) -> Option<Vec<u8>> {
-    let html = http.get(page.clone()).send().await.ok()?.text().await.ok()?;
+async fn fetch_favicon(http: &HttpClient, page: Url) -> Option<Vec<u8>> {
+    let html = http.get(page.clone()).send().await.ok()?.text();
     let advertised = html
         .split("<link")
         .skip(1)
@@ -820,10 +1506,10 @@ async fn fetch_favicon(http: &Client, page: Url) -> Option<Vec<u8>> {
         Url::parse(&format!("https://{name}.{suffix}/favicon.ico")).ok()
     })();
     for icon in advertised.into_iter().chain(fallback) {
-        let Some(bytes) = http.get(icon).send().await.ok().and_then(|response| response.error_for_status().ok()) else {
+        let Some(response) = http.get(icon).send().await.ok().and_then(|response| response.error_for_status().ok()) else {
             continue;
         };
-        let Ok(bytes) = bytes.bytes().await else { continue };
+        let bytes = response.bytes();
         if let Ok(Some(pixels)) = spawn_blocking(move || load_raster(&bytes)).await {
             return Some(pixels);
         }
@@ -874,3 +1560,74 @@ fn resize_icon(image: &image::DynamicImage) -> Vec<u8> {
     let raster = image.resize_to_fill(ICON_PX, ICON_PX, FilterType::Triangle);
     raster.into_rgba8().into_raw()
 }
+
+#[cfg(all(test, feature = "cpu"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn initials_find_their_words() {
+        assert!(fuzzy_score("ff", "Firefox").is_some());
+        assert!(fuzzy_score("vsc", "Visual Studio Code").is_some());
+        assert!(fuzzy_score("fx", "Files").is_none());
+    }
+
+    #[test]
+    fn case_and_spaces_in_the_query_are_ignored() {
+        assert_eq!(fuzzy_score("VIS stu", "Visual Studio Code"), fuzzy_score("visstu", "Visual Studio Code"));
+    }
+
+    #[test]
+    fn empty_query_matches_everything_equally() {
+        assert_eq!(fuzzy_score("", "Firefox"), Some(0));
+        assert_eq!(fuzzy_score("  ", "Files"), Some(0));
+    }
+
+    #[test]
+    fn word_starts_beat_letters_inside_words() {
+        let starts = fuzzy_score("vsc", "Visual Studio Code").unwrap();
+        let inside = fuzzy_score("vsc", "Envisioned Scene").unwrap();
+        assert!(starts > inside);
+        assert!(fuzzy_score("ss", "SimpleScreenRecorder").unwrap() > fuzzy_score("ss", "Passwords").unwrap());
+    }
+
+    #[test]
+    fn runs_and_early_matches_rank_higher() {
+        assert!(fuzzy_score("code", "Code").unwrap() > fuzzy_score("code", "Cloud Desktop").unwrap());
+        assert!(fuzzy_score("term", "Terminal").unwrap() > fuzzy_score("term", "GNOME Terminal").unwrap());
+    }
+
+    fn titles(output: &str) -> Vec<String> {
+        parse_command_rows("test", output.as_bytes()).into_iter().map(|(row, _)| row.title).collect()
+    }
+
+    #[test]
+    fn command_rows_parse_from_a_json_array() {
+        let rows = parse_command_rows("test", br#"[{"title": "One", "action": "one", "detail": "first"}, {"title": "Two", "action": "two"}]"#);
+        assert_eq!(rows.len(), 2);
+        assert_eq!((rows[0].0.title.as_str(), rows[0].0.detail.as_str(), rows[0].0.action.as_str()), ("One", "first", "one"));
+        assert!(rows[1].0.detail.is_empty() && rows[1].0.alternate.is_none());
+    }
+
+    #[test]
+    fn command_rows_parse_from_json_lines() {
+        assert_eq!(
+            titles("{\"title\": \"One\", \"action\": \"one\"}\n\n{\"title\": \"Two\", \"action\": \"two\"}\n"),
+            ["One", "Two"]
+        );
+    }
+
+    #[test]
+    fn malformed_command_rows_show_nothing() {
+        assert!(titles("{\"title\": \"One\", \"action\": \"one\"}\n{\"title\": \"No action\"}").is_empty());
+        assert!(titles("not json").is_empty());
+    }
+
+    #[test]
+    fn command_rows_stop_at_the_visible_limit() {
+        let output = (0..MAX_VISIBLE + 3)
+            .map(|index| format!("{{\"title\": \"{index}\", \"action\": \"\"}}\n"))
+            .collect::<String>();
+        assert_eq!(titles(&output).len(), MAX_VISIBLE);
+    }
+}
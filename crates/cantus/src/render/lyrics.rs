@@ -1,31 +1,95 @@
-use crate::render::{FrameData, smoothstep, text};
+use crate::render::{FrameData, PANEL_START, karaoke, smoothstep, text};
 use isthmus::{glam::Vec4, spirv_std::arch::kill};
 
 #[cfg(feature = "cpu")]
 use {
     crate::{
-        app::music::{Enrichment, MusicBackend, PlaybackState, Track},
+        app::{
+            config::{self, LyricsAnnotation},
+            interaction::Rect,
+            music::{Enrichment, MusicBackend, PlaybackState, Track},
+        },
         render::{
-            PANEL_START,
-            cpu::{Frame, Passes},
+            GAP, PADDING,
+            cpu::{Frame, Passes, approach},
+            karaoke::KaraokePass,
             text::TextStyle,
         },
     },
-    isthmus::glam::vec2,
-    std::time::Instant,
+    isthmus::glam::{Vec3, vec2},
+    serde::{Deserialize, Serialize},
+    std::{collections::HashMap, fs, time::Instant},
+    tracing::warn,
 };
 
 pub const EXTENSION: f32 = 10.0;
+/// Vertical step between the lyric lanes: lead vocals, duet or background vocals, then annotations.
+const LANE_OFFSET: f32 = 8.0;
+/// Extra room the strip takes while annotations are shown, for their lane.
+pub const ANNOTATION_EXTENSION: f32 = LANE_OFFSET;
+#[cfg(feature = "cpu")]
+const LANES: usize = 3;
+/// Annotations take the last lane, so they never collide with duet or background vocals.
+#[cfg(feature = "cpu")]
+const ANNOTATION_LANE: usize = LANES - 1;
 #[cfg(feature = "cpu")]
 pub(crate) const TEXT_GLYPHS: usize = 4_096;
+#[cfg(feature = "cpu")]
+const OFFSETS_FILE: &str = "cantus_lyrics_offsets.json";
+#[cfg(feature = "cpu")]
+const SUNG_COLOR: Vec3 = Vec3::new(1.0, 0.878, 0.824);
+#[cfg(feature = "cpu")]
+const UNSUNG_COLOR: Vec3 = Vec3::splat(0.6);
 
 #[cfg(feature = "cpu")]
+#[derive(Deserialize, Serialize)]
 pub struct LyricSegment {
     pub start_ms: f32,
     pub end_ms: f32,
     pub text: String,
     pub lane: usize,
     pub line_end: bool,
+    /// Set on a translation or romanisation of the line it starts with, shown aligned beneath it.
+    #[serde(default)]
+    pub annotation: Option<LyricsAnnotation>,
+}
+
+/// A change to the playing track's lyric timing, asked for from the launcher.
+#[cfg(feature = "cpu")]
+#[derive(Clone, Copy)]
+pub enum LyricsShift {
+    /// Delays the lyrics by this many milliseconds; negative values show them earlier.
+    By(f32),
+    Reset,
+}
+
+#[cfg(feature = "cpu")]
+impl LyricsShift {
+    /// Reads a launcher command such as `lyrics +50ms`, `lyrics -0.2s`, `lyrics 100` (milliseconds) or `lyrics reset`.
+    pub fn command(query: &str) -> Option<Self> {
+        let (keyword, argument) = query.split_once(char::is_whitespace)?;
+        if !keyword.eq_ignore_ascii_case("lyrics") {
+            return None;
+        }
+        let argument = argument.trim().to_ascii_lowercase();
+        if argument == "reset" {
+            return Some(Self::Reset);
+        }
+        let delay_ms = match (argument.strip_suffix("ms"), argument.strip_suffix('s')) {
+            (Some(milliseconds), _) => milliseconds.trim().parse::<f32>().ok()?,
+            (None, Some(seconds)) => seconds.trim().parse::<f32>().ok()? * 1000.0,
+            (None, None) => argument.parse::<f32>().ok()?,
+        };
+        (delay_ms.is_finite() && delay_ms.abs() >= 1.0).then_some(Self::By(delay_ms))
+    }
+
+    pub fn describe(self) -> String {
+        match self {
+            Self::By(delay_ms) if delay_ms > 0.0 => format!("Delay lyrics by {delay_ms} ms"),
+            Self::By(delay_ms) => format!("Show lyrics {} ms earlier", -delay_ms),
+            Self::Reset => String::from("Reset lyrics timing"),
+        }
+    }
 }
 
 #[isthmus::pass]
@@ -33,14 +97,34 @@ pub struct LyricsPass {
     lines: isthmus::Instances<Self>,
     enrichment: Enrichment,
     music: MusicBackend,
+    /// Per-track lyric delays in milliseconds, keyed by URI; negative values show lyrics early.
+    offsets: HashMap<String, f32>,
+    /// Karaoke panel reveal progress, and whether a click on the strip holds it open.
+    expansion: f32,
+    pinned: bool,
 }
 
 #[cfg(feature = "cpu")]
 #[derive(Default)]
 pub(crate) struct Lyrics {
-    lines: [text::ShapedLine; 2],
+    lines: [text::ShapedLine; LANES],
     timeline: Vec<(f32, f32)>,
     span: f32,
+    /// Sung lines in order, for the karaoke panel.
+    rows: Vec<LyricRow>,
+}
+
+#[cfg(feature = "cpu")]
+struct LyricRow {
+    start_ms: f32,
+    words: Vec<LyricWord>,
+}
+
+#[cfg(feature = "cpu")]
+struct LyricWord {
+    text: String,
+    start_ms: f32,
+    end_ms: f32,
 }
 
 #[cfg(feature = "cpu")]
@@ -51,8 +135,8 @@ impl Lyrics {
     const MUSIC_GAP_MS: f32 = 5_000.0;
     const LINE_GAP: f32 = 14.0;
 
-    pub(crate) fn shape(mut segments: Vec<LyricSegment>, duration_ms: f32, shaper: &text::Shaper) -> Option<Self> {
-        segments.retain(|segment| !segment.text.trim().is_empty());
+    pub(crate) fn shape(mut segments: Vec<LyricSegment>, duration_ms: f32, annotation: LyricsAnnotation, shaper: &text::Shaper) -> Option<Self> {
+        segments.retain(|segment| !segment.text.trim().is_empty() && segment.annotation.is_none_or(|kind| kind == annotation));
         segments.sort_by(|left, right| left.start_ms.total_cmp(&right.start_ms));
         if segments.is_empty() {
             return None;
@@ -69,6 +153,7 @@ impl Lyrics {
                     text: "♪".into(),
                     lane: 0,
                     line_end: true,
+                    annotation: None,
                 });
             }
             vocal_end = vocal_end.max(segment.end_ms);
@@ -76,20 +161,49 @@ impl Lyrics {
         segments.extend(music);
         segments.sort_by(|left, right| left.start_ms.total_cmp(&right.start_ms));
 
-        let mut positioned = [Vec::new(), Vec::new()];
+        let mut positioned = [const { Vec::new() }; LANES];
         let mut timeline = vec![(0.0, 0.0)];
-        let mut cursors = [0.0f32; 2];
+        let mut cursors = [0.0f32; LANES];
         let mut vocal_end = 0.0f32;
+        let (mut line_start, mut line_x) = (true, 0.0f32);
+        let mut rows: Vec<LyricRow> = Vec::new();
+        let mut open_rows = [None; 2];
         for segment in &segments {
             let silence = (segment.start_ms - vocal_end).max(0.0);
             if silence > 0.0 {
-                let cursor = cursors[0].max(cursors[1]) + silence * Self::SILENCE_SPEED;
-                cursors = [cursor; 2];
+                let cursor = cursors.into_iter().fold(0.0, f32::max) + silence * Self::SILENCE_SPEED;
+                cursors = [cursor; LANES];
             }
-            let lane = segment.lane.min(1);
+            let lane = if segment.annotation.is_some() {
+                ANNOTATION_LANE
+            } else {
+                segment.lane.min(ANNOTATION_LANE - 1)
+            };
             let text = segment.text.trim_start();
             let width = shaper.width(text, Self::STYLE);
-            let position = cursors[lane];
+            let position = if segment.annotation.is_some() { cursors[lane].max(line_x) } else { cursors[lane] };
+            if lane == 0 {
+                if line_start {
+                    line_x = position;
+                }
+                line_start = segment.line_end;
+            }
+            if segment.annotation.is_none() {
+                let word = LyricWord {
+                    text: text.to_owned(),
+                    start_ms: segment.start_ms,
+                    end_ms: segment.end_ms,
+                };
+                if let Some(row) = open_rows[lane] {
+                    rows[row].words.push(word);
+                } else {
+                    rows.push(LyricRow {
+                        start_ms: segment.start_ms,
+                        words: vec![word],
+                    });
+                }
+                open_rows[lane] = (!segment.line_end).then(|| open_rows[lane].unwrap_or(rows.len() - 1));
+            }
             positioned[lane].push((text, position));
             cursors[lane] += width + if segment.line_end { Self::LINE_GAP } else { 0.0 };
             vocal_end = vocal_end.max(segment.end_ms);
@@ -97,18 +211,20 @@ impl Lyrics {
                 timeline.push((f32::midpoint(segment.start_ms, segment.end_ms), position + width * 0.5));
             }
         }
-        let lines = [0, 1].map(|lane| shaper.shape_positioned(positioned[lane].iter().copied(), Self::STYLE, TEXT_GLYPHS));
-        let position = cursors[0].max(cursors[1]) + (duration_ms - vocal_end).max(0.0) * Self::SILENCE_SPEED;
+        let lines = positioned.map(|words| shaper.shape_positioned(words, Self::STYLE, TEXT_GLYPHS));
+        let position = cursors.into_iter().fold(0.0, f32::max) + (duration_ms - vocal_end).max(0.0) * Self::SILENCE_SPEED;
         timeline.push((duration_ms.max(vocal_end), position));
         timeline.sort_by(|left, right| left.0.total_cmp(&right.0));
         Some(Self {
             lines,
             timeline,
             span: position + Self::SONG_GAP,
+            rows,
         })
     }
 
-    fn position(&self, time: f32) -> f32 {
+    fn position(&self, time: f32, offset_ms: f32) -> f32 {
+        let time = time - offset_ms;
         let upper = self.timeline.partition_point(|&(at, _)| at <= time);
         if upper == 0 {
             return self.timeline.first().map_or(0.0, |&(_, x)| x);
@@ -125,17 +241,40 @@ impl Lyrics {
 #[cfg(feature = "cpu")]
 mod provider {
     use super::LyricSegment;
-    use crate::app::music::TrackId;
+    use crate::app::{
+        cache::TrackCache,
+        config::{Config, LyricsAnnotation, LyricsProvider, Services, expand_path},
+        http::HttpClient,
+        music::{Health, MusicBackend, MusicResult, TrackId},
+    };
+    use parking_lot::Mutex;
     use quick_xml::{
         Reader, XmlVersion,
         escape::unescape,
         events::{BytesStart, Event},
     };
-    use reqwest::Client;
+    use reqwest::StatusCode;
     use serde::Deserialize;
-    use std::mem;
+    use std::{
+        collections::HashMap,
+        fs, mem,
+        path::PathBuf,
+        sync::Arc,
+        time::{Duration, Instant},
+    };
+    use tokio::task::spawn_blocking;
+    use tracing::warn;
 
-    const API: &str = "https://lyrics-api.binimum.org/";
+    const LOCAL_SEARCH_DEPTH: usize = 3;
+    /// How long the listing of `.lrc` files is reused before the lyrics directories are walked again.
+    const LOCAL_INDEX_LIFETIME: Duration = Duration::from_secs(60);
+    const CACHE: TrackCache = TrackCache {
+        directory: "lyrics",
+        found_expiry: Duration::from_secs(30 * 24 * 60 * 60),
+        missing_expiry: Duration::from_secs(3 * 24 * 60 * 60),
+    };
+    /// Spotify's lyrics come through its own client rather than a URL, so it is named on its own in [`Health`].
+    const SPOTIFY_ENDPOINT: &str = "spotify";
 
     pub struct LyricsRequest {
         pub uri: String,
@@ -158,29 +297,259 @@ mod provider {
         timing_type: String,
     }
 
-    pub async fn fetch(http: &Client, query: &LyricsRequest) -> Option<Vec<LyricSegment>> {
-        let result = http
-            .get(API)
-            .query(&[
-                ("track", query.name.clone()),
-                ("artist", query.artist.clone()),
-                ("album", query.album.clone()),
-                ("duration", (query.duration_ms / 1000).to_string()),
-            ])
-            .send()
-            .await
-            .ok()?
-            .error_for_status()
-            .ok()?
-            .json::<SearchResponse>()
-            .await
-            .ok()?
-            .results
-            .into_iter()
-            .find(|result| result.timing_type == "word")?;
-        let source = http.get(result.url).send().await.ok()?.error_for_status().ok()?.text().await.ok()?;
-        let segments = parse(&source);
-        (!segments.is_empty()).then_some(segments)
+    #[derive(Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    struct LrclibRecord {
+        synced_lyrics: Option<String>,
+    }
+
+    /// The configured lyrics providers, tried in order until one has lyrics for the track.
+    pub struct LyricsChain {
+        pub annotation: LyricsAnnotation,
+        providers: Vec<LyricsProvider>,
+        directories: Vec<PathBuf>,
+        binimum_url: String,
+        lrclib_url: String,
+        /// `.lrc` files under `directories` by lowercase file name, and when they were listed.
+        local_index: Mutex<Option<(Instant, Arc<HashMap<String, PathBuf>>)>>,
+    }
+
+    impl LyricsChain {
+        /// Providers whose service is disabled are left out of the chain.
+        pub fn new(config: &Config) -> Self {
+            let binimum_url = Services::endpoint(&config.services.binimum);
+            let lrclib_url = Services::endpoint(&config.services.lrclib);
+            Self {
+                annotation: config.lyrics_annotation,
+                providers: config
+                    .lyrics_providers
+                    .iter()
+                    .copied()
+                    .filter(|provider| match provider {
+                        LyricsProvider::Binimum => binimum_url.is_some(),
+                        LyricsProvider::Lrclib => lrclib_url.is_some(),
+                        LyricsProvider::Local | LyricsProvider::Spotify => true,
+                    })
+                    .collect(),
+                directories: config.lyrics_directories.iter().map(|directory| expand_path(directory)).collect(),
+                binimum_url: binimum_url.unwrap_or_default(),
+                lrclib_url: lrclib_url.unwrap_or_default(),
+                local_index: Mutex::new(None),
+            }
+        }
+
+        /// Returns the first provider with lyrics, or `None` when every provider answered without any.
+        /// Online answers, including that none had lyrics, are cached on disk per track.
+        ///
+        /// # Errors
+        /// Returns when to ask again when a provider failed or was paused and none had lyrics.
+        pub async fn fetch(&self, http: &HttpClient, music: &MusicBackend, health: &Health, query: &LyricsRequest) -> Result<Option<(LyricsProvider, Vec<LyricSegment>)>, Instant> {
+            let cached = CACHE.read::<(LyricsProvider, Vec<LyricSegment>)>(&query.uri).await;
+
+            // Local files are read fresh each time so edits to them show up, unless a provider ranked ahead of them has cached lyrics.
+            let rank = |provider| self.providers.iter().position(|&configured| configured == provider);
+            if let Some(local_rank) = rank(LyricsProvider::Local)
+                && !cached
+                    .as_ref()
+                    .and_then(|cached| rank(cached.as_ref()?.0))
+                    .is_some_and(|cached_rank| cached_rank < local_rank)
+            {
+                match self.local(query).await {
+                    Ok(segments) if !segments.is_empty() => return Ok(Some((LyricsProvider::Local, segments))),
+                    Ok(_) => {}
+                    Err(error) => warn!(%error, track = query.name, "Reading local lyrics failed"),
+                }
+            }
+            if let Some(cached) = cached {
+                return Ok(cached);
+            }
+
+            let found = self.fetch_uncached(http, music, health, query).await?;
+            CACHE.store(&query.uri, found.as_ref());
+            Ok(found)
+        }
+
+        /// Asks the online providers in order, skipping those [`Health`] has paused; local files are handled by [`Self::fetch`] around the cache.
+        async fn fetch_uncached(
+            &self,
+            http: &HttpClient,
+            music: &MusicBackend,
+            health: &Health,
+            query: &LyricsRequest,
+        ) -> Result<Option<(LyricsProvider, Vec<LyricSegment>)>, Instant> {
+            let mut retry_at = None::<Instant>;
+            let mut wait_until = |at: Instant| retry_at = Some(retry_at.map_or(at, |retry_at| retry_at.min(at)));
+            for &provider in &self.providers {
+                let endpoint = match provider {
+                    LyricsProvider::Local => continue,
+                    LyricsProvider::Binimum => self.binimum_url.as_str(),
+                    LyricsProvider::Lrclib => self.lrclib_url.as_str(),
+                    LyricsProvider::Spotify => SPOTIFY_ENDPOINT,
+                };
+                if let Some(paused_until) = health.paused_until(endpoint, Instant::now()) {
+                    wait_until(paused_until);
+                    continue;
+                }
+                let result = match provider {
+                    LyricsProvider::Local => continue,
+                    LyricsProvider::Binimum => self.binimum(http, query).await,
+                    LyricsProvider::Lrclib => self.lrclib(http, query).await,
+                    LyricsProvider::Spotify => match query.track_id {
+                        Some(id) => music.lyrics(id).await,
+                        None => continue,
+                    },
+                };
+                match result {
+                    Ok(segments) => {
+                        health.succeeded(endpoint);
+                        if !segments.is_empty() {
+                            return Ok(Some((provider, segments)));
+                        }
+                    }
+                    Err(error) => {
+                        warn!(%error, ?provider, track = query.name, "Lyrics provider failed");
+                        wait_until(health.failed(endpoint));
+                    }
+                }
+            }
+            retry_at.map_or(Ok(None), Err)
+        }
+
+        async fn local(&self, query: &LyricsRequest) -> MusicResult<Vec<LyricSegment>> {
+            let names = [format!("{} - {}.lrc", query.artist, query.name), format!("{}.lrc", query.name)].map(|name| name.to_lowercase());
+            let index = self.local_index().await?;
+            let Some(path) = names.iter().find_map(|name| index.get(name)).cloned() else {
+                return Ok(Vec::new());
+            };
+            Ok(spawn_blocking(move || fs::read_to_string(path).map(|source| parse_lrc(&source)).unwrap_or_default()).await?)
+        }
+
+        /// The `.lrc` files under the lyrics directories, walked again once the listing is [`LOCAL_INDEX_LIFETIME`] old.
+        async fn local_index(&self) -> MusicResult<Arc<HashMap<String, PathBuf>>> {
+            if let Some((listed, index)) = &*self.local_index.lock()
+                && listed.elapsed() < LOCAL_INDEX_LIFETIME
+            {
+                return Ok(Arc::clone(index));
+            }
+            let directories = self.directories.clone();
+            let index = Arc::new(spawn_blocking(move || index_lrc(&directories)).await?);
+            *self.local_index.lock() = Some((Instant::now(), Arc::clone(&index)));
+            Ok(index)
+        }
+
+        async fn binimum(&self, http: &HttpClient, query: &LyricsRequest) -> MusicResult<Vec<LyricSegment>> {
+            let response = http
+                .get(format!("{}/", self.binimum_url))
+                .query(&[
+                    ("track", query.name.clone()),
+                    ("artist", query.artist.clone()),
+                    ("album", query.album.clone()),
+                    ("duration", (query.duration_ms / 1000).to_string()),
+                ])
+                .send()
+                .await?;
+            if response.status() == StatusCode::NOT_FOUND {
+                return Ok(Vec::new());
+            }
+            let results = response.error_for_status()?.json::<SearchResponse>()?.results;
+            let Some(result) = results.into_iter().find(|result| result.timing_type == "word") else {
+                return Ok(Vec::new());
+            };
+            let source = http.get(result.url).send().await?.error_for_status()?.text();
+            Ok(parse(&source))
+        }
+
+        async fn lrclib(&self, http: &HttpClient, query: &LyricsRequest) -> MusicResult<Vec<LyricSegment>> {
+            let response = http
+                .get(format!("{}/api/get", self.lrclib_url))
+                .query(&[
+                    ("track_name", query.name.clone()),
+                    ("artist_name", query.artist.clone()),
+                    ("album_name", query.album.clone()),
+                    ("duration", (query.duration_ms / 1000).to_string()),
+                ])
+                .send()
+                .await?;
+            if response.status() == StatusCode::NOT_FOUND {
+                return Ok(Vec::new());
+            }
+            let record = response.error_for_status()?.json::<LrclibRecord>()?;
+            Ok(record.synced_lyrics.as_deref().map(parse_lrc).unwrap_or_default())
+        }
+    }
+
+    /// Lists the `.lrc` files under `directories` by lowercase file name, searching a few levels deep; the first found of a name wins.
+    fn index_lrc(directories: &[PathBuf]) -> HashMap<String, PathBuf> {
+        let mut index = HashMap::new();
+        let mut pending = directories.iter().rev().map(|directory| (directory.clone(), 0)).collect::<Vec<_>>();
+        while let Some((directory, depth)) = pending.pop() {
+            let Ok(entries) = fs::read_dir(&directory) else {
+                continue;
+            };
+            for entry in entries.flatten() {
+                let path = entry.path();
+                if entry.file_type().is_ok_and(|kind| kind.is_dir()) {
+                    if depth < LOCAL_SEARCH_DEPTH {
+                        pending.push((path, depth + 1));
+                    }
+                } else if let Some(name) = entry.file_name().to_str().map(str::to_lowercase).filter(|name| name.ends_with(".lrc")) {
+                    index.entry(name).or_insert(path);
+                }
+            }
+        }
+        index
+    }
+
+    /// Parses LRC lines, splitting them into words where enhanced-LRC `<mm:ss.xx>` timings are present.
+    fn parse_lrc(source: &str) -> Vec<LyricSegment> {
+        const LAST_LINE_MS: f32 = 5_000.0;
+
+        let mut offset_ms = 0.0;
+        let mut lines = Vec::new();
+        for line in source.lines() {
+            let mut body = line.trim();
+            let mut starts = Vec::new();
+            while let Some((tag, rest)) = body.strip_prefix('[').and_then(|tagged| tagged.split_once(']')) {
+                if let Some(start_ms) = time(tag) {
+                    starts.push(start_ms);
+                } else if let Some(offset) = tag.strip_prefix("offset:") {
+                    offset_ms = offset.trim().parse().unwrap_or_default();
+                }
+                body = rest;
+            }
+            lines.extend(starts.into_iter().map(|start_ms| (start_ms, body)));
+        }
+        lines.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        let mut segments = Vec::new();
+        for (index, &(line_start_ms, body)) in lines.iter().enumerate() {
+            let line_end_ms = lines.get(index + 1).map_or(line_start_ms + LAST_LINE_MS, |next| next.0);
+            let mut parts = body.split('<');
+            let mut words = vec![(line_start_ms, parts.next().unwrap_or_default())];
+            words.extend(parts.filter_map(|part| {
+                let (stamp, word) = part.split_once('>')?;
+                Some((time(stamp)?, word))
+            }));
+            let line_start = segments.len();
+            for (word, &(start_ms, text)) in words.iter().enumerate() {
+                if text.trim().is_empty() {
+                    continue;
+                }
+                let end_ms = words.get(word + 1).map_or(line_end_ms, |next| next.0);
+                segments.push(LyricSegment {
+                    start_ms: start_ms - offset_ms,
+                    end_ms: end_ms - offset_ms,
+                    text: text.to_owned(),
+                    lane: 0,
+                    line_end: false,
+                    annotation: None,
+                });
+            }
+            if segments.len() > line_start {
+                segments.last_mut().unwrap().line_end = true;
+            }
+        }
+        segments
     }
 
     fn time(value: &str) -> Option<f32> {
@@ -209,11 +578,13 @@ mod provider {
         let mut line_text = String::new();
         let mut primary_agent = None;
         let mut span_roles = Vec::new();
+        let mut annotations: Vec<(LyricsAnnotation, String)> = Vec::new();
         loop {
             match reader.read_event() {
                 Ok(Event::Start(tag)) if tag.local_name().as_ref() == b"p" => {
                     span_roles.clear();
                     line_text.clear();
+                    annotations.clear();
                     line_time = attribute(&tag, b"begin").as_deref().and_then(time).zip(attribute(&tag, b"end").as_deref().and_then(time));
                     let agent = attribute(&tag, b"agent").unwrap_or_default();
                     let lane = usize::from(primary_agent.as_ref().is_some_and(|primary| primary != &agent));
@@ -225,11 +596,12 @@ mod provider {
                     let start = attribute(&tag, b"begin").as_deref().and_then(time);
                     let end = attribute(&tag, b"end").as_deref().and_then(time);
                     span_roles.push(match attribute(&tag, b"role").as_deref() {
-                        Some("x-bg") => (true, false),
-                        Some("x-translation" | "x-roman") => (false, true),
-                        _ => (false, false),
+                        Some("x-bg") => (true, None),
+                        Some("x-translation") => (false, Some(LyricsAnnotation::Translation)),
+                        Some("x-roman") => (false, Some(LyricsAnnotation::Romanization)),
+                        _ => (false, None),
                     });
-                    if !span_roles.iter().any(|&(_, ignored)| ignored)
+                    if !span_roles.iter().any(|&(_, annotation)| annotation.is_some())
                         && let Some(start_ms) = start
                     {
                         segments.push(LyricSegment {
@@ -238,16 +610,24 @@ mod provider {
                             text: String::new(),
                             lane: line_lane.unwrap() ^ usize::from(span_roles.iter().any(|&(background, _)| background)),
                             line_end: false,
+                            annotation: None,
                         });
                     }
                 }
-                Ok(Event::Text(value)) if line_lane.is_some() && !span_roles.iter().any(|&(_, ignored)| ignored) => {
+                Ok(Event::Text(value)) if line_lane.is_some() => {
                     let Ok(value) = value.decode() else {
                         return Vec::new();
                     };
                     let Ok(value) = unescape(&value) else {
                         return Vec::new();
                     };
+                    if let Some(annotation) = span_roles.iter().find_map(|&(_, annotation)| annotation) {
+                        match annotations.iter_mut().find(|(kind, _)| *kind == annotation) {
+                            Some((_, text)) => text.push_str(&value),
+                            None => annotations.push((annotation, value.into_owned())),
+                        }
+                        continue;
+                    }
                     line_text.push_str(&value);
                     if segments.len() > line_start {
                         let segment = &mut segments.last_mut().unwrap().text;
@@ -274,11 +654,26 @@ mod provider {
                             text: mem::take(&mut line_text),
                             lane: line_lane.unwrap_or_default(),
                             line_end: false,
+                            annotation: None,
                         });
                     }
                     if segments.len() > line_start {
                         segments.last_mut().unwrap().line_end = true;
                     }
+                    let line = &segments[line_start..];
+                    let span = line_time.or_else(|| Some((line.first()?.start_ms, line.iter().map(|segment| segment.end_ms).reduce(f32::max)?)));
+                    if let Some((start_ms, end_ms)) = span {
+                        for (annotation, text) in annotations.drain(..) {
+                            segments.push(LyricSegment {
+                                start_ms,
+                                end_ms,
+                                text: text.trim().to_owned(),
+                                lane: 1,
+                                line_end: true,
+                                annotation: Some(annotation),
+                            });
+                        }
+                    }
                     line_lane = None;
                     span_roles.clear();
                 }
@@ -289,10 +684,56 @@ mod provider {
         }
         segments
     }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn spans(segments: &[LyricSegment]) -> Vec<(f32, f32, &str, bool)> {
+            segments
+                .iter()
+                .map(|segment| (segment.start_ms, segment.end_ms, segment.text.as_str(), segment.line_end))
+                .collect()
+        }
+
+        #[test]
+        fn lines_run_until_the_next_and_the_last_holds_for_five_seconds() {
+            let segments = parse_lrc("[ti:Song]\n[00:01.50]First line\n[00:04.00]Second line\n");
+            assert_eq!(spans(&segments), [(1_500.0, 4_000.0, "First line", true), (4_000.0, 9_000.0, "Second line", true)]);
+        }
+
+        #[test]
+        fn repeated_timestamps_are_sorted_and_blank_lines_only_end_the_previous() {
+            let segments = parse_lrc("[00:10.00][00:02.00]Chorus\n[00:05.00]\n[01:00.00]Verse");
+            assert_eq!(
+                spans(&segments),
+                [
+                    (2_000.0, 5_000.0, "Chorus", true),
+                    (10_000.0, 60_000.0, "Chorus", true),
+                    (60_000.0, 65_000.0, "Verse", true)
+                ]
+            );
+        }
+
+        #[test]
+        fn offset_tag_shifts_every_line_earlier() {
+            let segments = parse_lrc("[offset:500]\n[00:01.00]Early");
+            assert_eq!(spans(&segments), [(500.0, 5_500.0, "Early", true)]);
+        }
+
+        #[test]
+        fn enhanced_timings_split_a_line_into_words() {
+            let segments = parse_lrc("[00:01.00]<00:01.00>Hello <00:01.50>there\n[00:03.00]Next");
+            assert_eq!(
+                spans(&segments),
+                [(1_000.0, 1_500.0, "Hello ", false), (1_500.0, 3_000.0, "there", true), (3_000.0, 8_000.0, "Next", true)]
+            );
+        }
+    }
 }
 
 #[cfg(feature = "cpu")]
-pub(crate) use provider::{LyricsRequest, fetch};
+pub(crate) use provider::{LyricsChain, LyricsRequest};
 
 #[isthmus::pass]
 impl LyricsPass {
@@ -302,12 +743,94 @@ impl LyricsPass {
             lines: passes.instances_with_capacity((placed_glyphs, glyphs, edges), 6),
             enrichment,
             music,
+            offsets: fs::read(config::directory().join(OFFSETS_FILE))
+                .ok()
+                .and_then(|bytes| serde_json::from_slice(&bytes).inspect_err(|error| warn!(%error, "Failed to parse lyrics offsets")).ok())
+                .unwrap_or_default(),
+            expansion: 0.0,
+            pinned: false,
+        }
+    }
+
+    /// Applies `shift` to `uri`'s lyric delay and saves the offsets.
+    fn shift(&mut self, uri: &str, shift: LyricsShift) {
+        let offset = self.offsets.entry(uri.to_owned()).or_default();
+        match shift {
+            LyricsShift::By(delay_ms) => *offset += delay_ms,
+            LyricsShift::Reset => *offset = 0.0,
+        }
+        if offset.abs() < 1.0 {
+            self.offsets.remove(uri);
+        }
+        let Ok(contents) = serde_json::to_vec(&self.offsets) else {
+            return;
+        };
+        self.enrichment.background.run(move || {
+            if let Err(error) = fs::write(config::directory().join(OFFSETS_FILE), contents) {
+                warn!(%error, "Failed to save lyrics offsets");
+            }
+        });
+    }
+
+    /// Lays out the karaoke panel around the lyric line at `time_ms`, returning the start of a clicked line.
+    fn update_panel(&mut self, text: &mut text::Renderer, karaoke: &mut KaraokePass, lyrics: Option<&Lyrics>, time_ms: f32, strip: Rect, frame: &mut Frame<'_>) -> Option<f32> {
+        let rows = lyrics.map_or(&[][..], |lyrics| &lyrics.rows);
+        let strip = (!rows.is_empty()).then(|| frame.interaction.surface(strip));
+        if strip.as_ref().is_some_and(|strip| strip.clicked) {
+            self.pinned = !self.pinned;
+        }
+        let (min, max) = (karaoke.panel.origin, karaoke.panel.origin + karaoke.panel.size);
+        let over_panel = self.expansion > 0.5 && frame.interaction.contains(Rect::new(min.x, min.y, max.x, max.y));
+        let open = strip.is_some_and(|strip| self.pinned || strip.hovered || over_panel);
+        approach(&mut self.expansion, f32::from(open), frame.delta_time * 4.0);
+        karaoke.panel.expansion = smoothstep(0.0, 1.0, self.expansion);
+        karaoke.panel.hovered_row = -1;
+        if self.expansion <= 0.0 {
+            return None;
+        }
+
+        let focus = rows.partition_point(|row| row.start_ms <= time_ms).saturating_sub(1);
+        let slots: [Option<&LyricRow>; karaoke::ROWS] = [focus.checked_sub(1).and_then(|row| rows.get(row)), rows.get(focus), rows.get(focus + 1)];
+        let widths = slots.map(|row| row.map_or(0.0, |row| row.words.iter().map(|word| text.width(&word.text, karaoke::STYLE)).sum::<f32>()));
+        let screen = frame.shared.screen_size;
+        let width = (widths.into_iter().fold(0.0, f32::max) + PADDING * 4.0).max(karaoke::MIN_WIDTH).min(screen.x - GAP * 2.0);
+        let origin = vec2(
+            (frame.shared.playhead_x - width * 0.5).clamp(GAP, screen.x - GAP - width),
+            PANEL_START + frame.config.height + karaoke::TOP,
+        );
+        karaoke.panel.origin = origin;
+        karaoke.panel.size = vec2(width, karaoke::HEIGHT);
+
+        let mut seek = None;
+        for (slot, row) in slots.iter().enumerate() {
+            let Some(row) = row else {
+                continue;
+            };
+            let top = origin.y + PADDING + slot as f32 * karaoke::ROW_HEIGHT;
+            if self.expansion > 0.5 {
+                let response = frame.interaction.surface(Rect::new(origin.x, top, origin.x + width, top + karaoke::ROW_HEIGHT));
+                if response.hovered {
+                    karaoke.panel.hovered_row = slot as i32;
+                }
+                if response.clicked {
+                    seek = Some(row.start_ms);
+                }
+            }
+            let alpha = karaoke.panel.expansion * if slot == 1 { 1.0 } else { 0.55 };
+            let y = top + (karaoke::ROW_HEIGHT - karaoke::FONT_SIZE) * 0.5;
+            let mut x = origin.x + (width - widths[slot]) * 0.5;
+            for word in &row.words {
+                let sung = ((time_ms - word.start_ms) / (word.end_ms - word.start_ms).max(1.0)).clamp(0.0, 1.0);
+                let color = UNSUNG_COLOR.lerp(SUNG_COLOR, sung).extend(alpha);
+                self.lines.push(text.left(&word.text, karaoke::STYLE, y, x).with_color(color));
+                x += text.width(&word.text, karaoke::STYLE);
+            }
         }
+        seek
     }
 
-    pub fn update(&mut self, text: &mut text::Renderer, playback: &mut PlaybackState, frame: &Frame<'_>) {
+    pub fn update(&mut self, text: &mut text::Renderer, karaoke: Option<&mut KaraokePass>, playback: &mut PlaybackState, shift: Option<LyricsShift>, frame: &mut Frame<'_>) {
         const PREFETCH_TRACKS: usize = 4;
-        const LANE_OFFSET: f32 = 8.0;
 
         let start = playback
             .timeline
@@ -323,6 +846,10 @@ impl LyricsPass {
 
         let Some((index, progress_ms)) = playback.timeline.track_at_playhead(&playback.queue) else {
             self.lines.clear();
+            self.expansion = 0.0;
+            if let Some(karaoke) = karaoke {
+                karaoke.panel.expansion = 0.0;
+            }
             return;
         };
         let visible = index.saturating_sub(1)..(index + 2).min(playback.queue.len());
@@ -337,11 +864,22 @@ impl LyricsPass {
                 .map_or_else(|| track.queue_span_ms() * Lyrics::SILENCE_SPEED, |lyrics| lyrics.span)
         };
         let current = &playback.queue[index];
+        let strip = Rect::new(0.0, PANEL_START + frame.config.height, frame.shared.screen_size.x, y + LANE_OFFSET * LANES as f32);
+        if let Some(shift) = shift {
+            self.shift(&current.uri, shift);
+        }
+        let offset_ms = self.offsets.get(&current.uri).copied().unwrap_or_default();
+        if let Some(karaoke) = karaoke
+            && let Some(start_ms) = self.update_panel(text, karaoke, current.runtime.lyrics.ready(), progress_ms - offset_ms, strip, frame)
+            && index == playback.timeline.index
+        {
+            frame.interaction.seek_to((start_ms + offset_ms).max(0.0) as u32);
+        }
         let progress = current
             .runtime
             .lyrics
             .ready()
-            .map_or(progress_ms * Lyrics::SILENCE_SPEED, |lyrics| lyrics.position(progress_ms));
+            .map_or(progress_ms * Lyrics::SILENCE_SPEED, |lyrics| lyrics.position(progress_ms, offset_ms));
         let mut x = frame.shared.playhead_x - progress;
         for track in &playback.queue[visible.start..index] {
             x -= span(track);
@@ -361,6 +899,14 @@ impl LyricsPass {
             }
             x += span(track);
         }
+
+        // With no lyrics at the playhead, the strip reports the enrichment sources that are down.
+        if current.runtime.lyrics.ready().is_none_or(|lyrics| lyrics.span <= 0.0)
+            && let Some(summary) = self.enrichment.health.summary(now)
+        {
+            let line = text.left(&summary, Lyrics::STYLE, y, frame.shared.playhead_x + GAP);
+            self.lines.push(line.with_color(UNSUNG_COLOR.extend(0.8)));
+        }
     }
 
     #[gpu]
@@ -380,8 +926,10 @@ impl LyricsPass {
         if frame.launcher_open > 0.5 {
             kill();
         }
-        let edge_fade = smoothstep(0.0, 32.0, pixel.x) * smoothstep(frame.screen_size.x, frame.screen_size.x - 32.0, pixel.x);
-        let emphasis = smoothstep(110.0, 0.0, (pixel.x - frame.playhead_x).abs());
+        // Karaoke panel lines sit below the strip and skip its playhead-relative effects.
+        let strip = if line.min.y < PANEL_START + frame.panel_height + karaoke::TOP { 1.0 } else { 0.0 };
+        let edge_fade = 1.0 - (1.0 - smoothstep(0.0, 32.0, pixel.x) * smoothstep(frame.screen_size.x, frame.screen_size.x - 32.0, pixel.x)) * strip;
+        let emphasis = smoothstep(110.0, 0.0, (pixel.x - frame.playhead_x).abs()) * strip;
         let mut emphasized = *line;
         emphasized.weight = (emphasized.weight + emphasis * 0.15).min(1.0);
         let distance = text::line_distance_scaled(emphasized, placed_glyphs, glyphs, edges, pixel, 1.0 + emphasis * 0.2);
@@ -389,8 +937,10 @@ impl LyricsPass {
 
         let outline = text::coverage(distance + 0.9) * 0.4;
         let alpha = fill + outline * (1.0 - fill);
-        let played = smoothstep(frame.playhead_x + 4.0, frame.playhead_x - 4.0, pixel.x);
-        let color = line.color.to_vec3().lerp(line.color.to_vec3() * 0.42, played);
-        (color * fill * edge_fade).extend(alpha * edge_fade)
+        let played = smoothstep(frame.playhead_x + 4.0, frame.playhead_x - 4.0, pixel.x) * strip;
+        let color = line.color.to_vec4();
+        let tint = color.truncate().lerp(color.truncate() * 0.42, played);
+        let fade = edge_fade * color.w;
+        (tint * fill * fade).extend(alpha * fade)
     }
 }
@@ -2,6 +2,7 @@
 pub mod atlas;
 #[cfg(feature = "cpu")]
 pub mod cpu;
+pub mod karaoke;
 pub mod launcher;
 pub mod lyrics;
 pub mod particles;
@@ -11,9 +12,13 @@ pub mod status;
 pub mod tempestas;
 pub mod text;
 pub mod track;
+pub mod visualiser;
 
 use isthmus::glam::{FloatExt, Vec2};
 
+#[cfg(target_arch = "spirv")]
+use isthmus::spirv_std::num_traits::Float;
+
 /// Gap between the top of the surface and the top of the bar.
 pub const PANEL_START: f32 = 6.0;
 /// Base spacing unit. Sizes and gaps should be whole multiples of it.
@@ -38,9 +43,20 @@ pub struct FrameData {
     /// Current hour at the detected weather location.
     pub weather_hour: f32,
     pub launcher_open: f32,
+    /// Progress from the latest detected beat of the playing audio towards the next, in 0..1.
+    pub beat_phase: f32,
+    /// Confidence in the detected beat, zero without a steady pulse or while paused.
+    pub beat_strength: f32,
     pub ripples: [RipplePulse; 4],
 }
 
+impl FrameData {
+    /// Kick-like envelope of the detected beat: full on each beat, decaying before the next.
+    pub fn beat_envelope(&self) -> f32 {
+        (-self.beat_phase * 6.0).exp()
+    }
+}
+
 #[isthmus::data]
 #[derive(Default)]
 pub struct RipplePulse {
@@ -28,6 +28,7 @@ pub const PARTICLE_COUNT: usize = 64;
 pub struct ParticlePass {
     instances: isthmus::Instances<Self>,
     accumulator: f32,
+    beat_phase: f32,
 }
 
 #[isthmus::data]
@@ -52,6 +53,7 @@ impl ParticlePass {
         Self {
             instances: passes.instances((), [Particle::default(); PARTICLE_COUNT]),
             accumulator: 0.0,
+            beat_phase: 0.0,
         }
     }
 
@@ -60,8 +62,12 @@ impl ParticlePass {
         const VELOCITY_Y: f32 = 5.0;
         const LIFETIME_START: f32 = 1.2;
         const LIFETIME_END: f32 = 1.5;
+        /// Extra particles released on each confidently detected beat.
+        const BEAT_BURST: f32 = 4.0;
 
         let time = frame.shared.time;
+        let on_beat = frame.shared.beat_phase < self.beat_phase;
+        self.beat_phase = frame.shared.beat_phase;
         if let Some(palette) = track.current_track_palette {
             let movement = playback.timeline.movement;
             self.accumulator = if movement.abs() > 0.00001 {
@@ -69,6 +75,9 @@ impl ParticlePass {
             } else {
                 0.0
             };
+            if on_beat {
+                self.accumulator += BEAT_BURST * frame.shared.beat_strength;
+            }
             let emit_count = self.accumulator.floor() as u8;
             self.accumulator -= f32::from(emit_count);
             let horizontal_bias = (movement.abs().powf(0.2) * movement.signum()).clamp(-3.0, 3.0);
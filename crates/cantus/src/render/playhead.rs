@@ -11,13 +11,14 @@ use isthmus::{
 
 #[cfg(feature = "cpu")]
 use crate::{
-    app::{interaction::Rect, music::PlaybackState},
+    app::{Background, interaction::Rect, music::PlaybackState},
     render::cpu::{Frame, Passes, approach},
 };
 
 #[isthmus::pass]
 pub struct PlayheadPass {
     pill: isthmus::Instance<Self>,
+    background: Background,
 }
 
 #[isthmus::data]
@@ -35,9 +36,10 @@ pub struct Varyings {
 
 #[isthmus::pass]
 impl PlayheadPass {
-    pub fn new(passes: &Passes<'_>) -> Self {
+    pub fn new(passes: &Passes<'_>, background: Background) -> Self {
         Self {
             pill: passes.instance((), PlayheadState::default()),
+            background,
         }
     }
 
@@ -53,13 +55,16 @@ impl PlayheadPass {
             .surface(Rect::from_center(vec2(playhead_x, PANEL_START + height * 0.5), vec2(height * 0.25, height * 0.5)));
         let speed = TRANSITION_SPEED * frame.delta_time;
         let last_toggle = (time - *last_toggle_time) / START_DURATION;
-        if !playhead.hovered && playback.playing && last_toggle < 1.0 {
+        // While another player's media interrupts the queue, the playhead controls that player instead.
+        let interruption = playback.interruption.as_mut().filter(|interruption| interruption.open);
+        let playing = interruption.as_ref().map_or(playback.playing, |interruption| interruption.playing);
+        if !playhead.hovered && playing && last_toggle < 1.0 {
             self.pill.bar_split = 1.0 - last_toggle;
             self.pill.icon_presence = 1.0 - last_toggle;
             approach(&mut self.pill.icon_morph, 1.0, speed * 1.5);
         } else {
-            let show_icon = f32::from(playhead.hovered || !playback.playing);
-            let play_icon = f32::from(playhead.hovered && !playback.playing);
+            let show_icon = f32::from(playhead.hovered || !playing);
+            let play_icon = f32::from(playhead.hovered && !playing);
             approach(&mut self.pill.bar_split, show_icon, speed);
             self.pill.icon_presence = self.pill.icon_presence.max(show_icon);
             approach(&mut self.pill.icon_presence, show_icon, speed);
@@ -67,7 +72,10 @@ impl PlayheadPass {
         }
 
         if playhead.clicked {
-            frame.interaction.toggle_playing(playback.playing);
+            match interruption {
+                Some(interruption) => interruption.toggle(&self.background),
+                None => frame.interaction.toggle_playing(playback.playing),
+            }
         }
     }
 
@@ -95,7 +103,9 @@ impl PlayheadPass {
         // Bar splits into two capsule segments straddling the center as bar_split grows.
         let bar_len = frame.panel_height * (0.5 - 0.375 * state.bar_split);
         let bar_center = (frame.panel_height - bar_len) * 0.5;
-        let dist_bar = sd_capsule_box(vec2(pause.y - bar_center, pause.x), bar_len * 0.5, 4.5);
+        // The bar swells on each detected beat, settling as it splits into the pause icon.
+        let pulse = frame.beat_envelope() * frame.beat_strength * (1.0 - state.bar_split);
+        let dist_bar = sd_capsule_box(vec2(pause.y - bar_center, pause.x), bar_len * 0.5, 4.5 + pulse * 1.5);
 
         let dx = (pause.x - 4.0 * state.bar_split).abs();
         let dy = (pause.y - frame.panel_height * 0.1).max(0.0);
@@ -110,6 +120,6 @@ impl PlayheadPass {
             kill();
         }
         let edge = smoothstep(-2.5, -1.0, dist_bar.min(dist_icon));
-        vec3(1.0, 0.878, 0.824).lerp(Vec3::splat(0.15), edge).extend(alpha)
+        vec3(1.0, 0.878, 0.824).lerp(Vec3::ONE, pulse * 0.4).lerp(Vec3::splat(0.15), edge).extend(alpha)
     }
 }
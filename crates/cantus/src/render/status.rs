@@ -21,6 +21,7 @@ use {
         app::{
             AppUpdater, Background,
             interaction::Rect,
+            music::{AudioTap, Mixer, step_volume},
             platform::{Current as Platform, Platform as _},
         },
         render::{
@@ -31,10 +32,7 @@ use {
     arrayvec::ArrayString,
     std::{
         fmt::Write,
-        sync::{
-            Arc,
-            atomic::{AtomicU32, Ordering},
-        },
+        sync::{Arc, atomic::Ordering},
     },
 };
 
@@ -136,7 +134,7 @@ pub struct StatusVaryings {
 pub struct StatusPass {
     pub pill: isthmus::Instance<Self>,
     pub(crate) temperature_targets: [f32; 2],
-    audio_spectrum: Arc<[AtomicU32; AUDIO_SPECTRUM_BANDS]>,
+    audio: Arc<AudioTap>,
     background: Background,
 }
 
@@ -383,9 +381,8 @@ fn action_icon(point: Vec2, time: f32, action: f32, hover: f32, pill: &StatusPil
 
 #[isthmus::pass]
 impl StatusPass {
-    pub(crate) fn new(passes: &Passes<'_>, text: &text::Renderer, background: &Background, updater: AppUpdater) -> Self {
-        let audio_spectrum = Arc::<[AtomicU32; AUDIO_SPECTRUM_BANDS]>::default();
-        Platform::start_status_monitor(background, updater, Arc::clone(&audio_spectrum));
+    pub(crate) fn new(passes: &Passes<'_>, text: &text::Renderer, background: &Background, updater: AppUpdater, audio: Arc<AudioTap>) -> Self {
+        Platform::start_status_monitor(background, updater);
         let pill = passes.instance(
             text.resources(),
             StatusPill {
@@ -398,19 +395,19 @@ impl StatusPass {
         Self {
             pill,
             temperature_targets: [0.0; 2],
-            audio_spectrum,
+            audio,
             background: background.clone(),
         }
     }
 
-    pub fn update(&mut self, text: &mut text::Renderer, frame: &mut Frame) {
+    pub fn update(&mut self, text: &mut text::Renderer, mixer: &mut Mixer, frame: &mut Frame) {
         let height = frame.config.height;
         let pill = &mut *self.pill;
         let temperature_blend = 1.0 - (-5.0 * frame.delta_time).exp();
         for (processor, target) in [&mut pill.cpu, &mut pill.gpu].into_iter().zip(self.temperature_targets) {
             processor.temperature += (target - processor.temperature) * temperature_blend;
         }
-        for (damped, level) in pill.audio_spectrum.iter_mut().zip(self.audio_spectrum.iter()) {
+        for (damped, level) in pill.audio_spectrum.iter_mut().zip(&self.audio.spectrum) {
             let target = f32::from_bits(level.load(Ordering::Relaxed));
             let response = if target > *damped { 18.0 } else { 6.0 };
             *damped += (target - *damped) * (1.0 - (-response * frame.delta_time).exp());
@@ -421,10 +418,10 @@ impl StatusPass {
         let x = pill_x(frame.shared.screen_size.x, width);
         let scroll = frame.interaction.scroll(section_rect(pill, x, height, StatusSection::Audio));
         if scroll != 0 {
-            let sign = pill.volume.signum();
-            pill.volume = (pill.volume.abs() - scroll as f32 * 0.05).saturate() * sign;
-            Platform::set_volume(pill.volume.abs());
+            mixer.sink = step_volume(mixer.sink, scroll);
+            Platform::set_volume(None, mixer.sink.abs());
         }
+        pill.volume = mixer.sink;
 
         let buttons = StatusSection::POWER_ACTIONS.map(|section| frame.interaction.surface(section_rect(pill, x, height, section)));
         pill.power_hover = buttons.iter().position(|response| response.hovered).map_or(-1, |action| action as i32);
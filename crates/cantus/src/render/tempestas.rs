@@ -18,7 +18,10 @@ use {
     crate::{
         app::{
             AppUpdater, Background,
+            config::Config,
+            http::HttpClient,
             interaction::{InteractionState, Rect},
+            timers::Timers,
         },
         render::{
             cpu::{Frame, Passes, approach},
@@ -33,7 +36,6 @@ use {
         civil::{DateTime, Time},
         tz::{Offset, TimeZone},
     },
-    reqwest::Client,
     std::{fmt::Write, mem},
     tracing::warn,
 };
@@ -53,7 +55,7 @@ const TEXT_COLUMNS: usize = 24;
 const TEXT_ROWS: usize = 36;
 #[cfg(feature = "cpu")]
 mod host {
-    use super::{MAX_WORLD_CLOCKS, TextStyle, UNIT, Vec2, WIDTH};
+    use super::{MAX_WORLD_CLOCKS, TextStyle, UNIT, Vec2, Vec3, WIDTH};
 
     pub const WEEKDAY_COUNT: usize = 7;
     pub const GRID_CELLS: usize = WEEKDAY_COUNT * 6;
@@ -64,17 +66,19 @@ mod host {
     pub const WEEKDAYS: [&str; WEEKDAY_COUNT] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
     pub const ORDINALS: [&str; 10] = ["th", "st", "nd", "rd", "th", "th", "th", "th", "th", "th"];
     pub const TEXT_CELLS: usize = super::TEXT_COLUMNS * super::TEXT_ROWS;
-    pub const MAX_TEXT_LINES: usize = 17 + (super::HOURLY_FORECASTS + super::DAILY_FORECASTS + MAX_WORLD_CLOCKS) * 2 + WEEKDAY_COUNT + GRID_CELLS;
+    pub const MAX_TEXT_LINES: usize = 18 + (super::HOURLY_FORECASTS + super::DAILY_FORECASTS + MAX_WORLD_CLOCKS) * 2 + WEEKDAY_COUNT + GRID_CELLS;
     pub const DETAILS_STYLE: TextStyle = TextStyle::new(14.0, 700.0);
     pub const WEATHER_STYLE: TextStyle = TextStyle::new(24.0, 600.0);
     pub const TITLE_STYLE: TextStyle = TextStyle::new(20.0, 750.0);
     pub const CLOCK_STYLE: TextStyle = TextStyle::new(12.0, 700.0);
+    pub const TIMER_STYLE: TextStyle = TextStyle::new(12.0, 750.0);
+    pub const ALERT_COLOR: Vec3 = Vec3::new(1.0, 0.42, 0.42);
 }
 
 #[cfg(feature = "cpu")]
 use host::{
-    CLOCK_STYLE, DETAILS_STYLE, GRID_CELLS, GRID_ROW_HEIGHT, GRID_TOP_Y, MAX_TEXT_LINES, ORDINALS, TEXT_CELLS, TITLE, TITLE_STYLE, WEATHER_STYLE, WEEKDAY_COUNT, WEEKDAY_Y,
-    WEEKDAYS,
+    ALERT_COLOR, CLOCK_STYLE, DETAILS_STYLE, GRID_CELLS, GRID_ROW_HEIGHT, GRID_TOP_Y, MAX_TEXT_LINES, ORDINALS, TEXT_CELLS, TIMER_STYLE, TITLE, TITLE_STYLE, WEATHER_STYLE,
+    WEEKDAY_COUNT, WEEKDAY_Y, WEEKDAYS,
 };
 
 #[isthmus::data]
@@ -419,9 +423,10 @@ fn sample_forecast(pixel: Vec2, frame: &FrameData, pill: &WeatherSurface) -> For
 
 #[isthmus::pass]
 impl TempestasPass {
-    pub(crate) fn new(passes: &Passes<'_>, text: &text::Renderer, timezones: &[String], background: &Background, updater: AppUpdater, http: Client) -> Self {
-        let mut forecast_timezones = Vec::with_capacity(timezones.len());
-        let timezones: ArrayVec<_, MAX_WORLD_CLOCKS> = timezones
+    pub(crate) fn new(passes: &Passes<'_>, text: &text::Renderer, config: &Config, background: &Background, updater: AppUpdater, http: HttpClient) -> Self {
+        let mut forecast_timezones = Vec::with_capacity(config.timezones.len());
+        let timezones: ArrayVec<_, MAX_WORLD_CLOCKS> = config
+            .timezones
             .iter()
             .filter_map(|name| {
                 let timezone = TimeZone::get(name).inspect_err(|error| warn!(timezone = name, %error, "Ignoring invalid timezone")).ok()?;
@@ -433,7 +438,7 @@ impl TempestasPass {
                 })
             })
             .collect();
-        monitor::start(forecast_timezones, background, updater, http);
+        monitor::start(forecast_timezones, &config.services, background, updater, http);
         let text_lines = passes.storage_with_capacity("Tempestas Text", MAX_TEXT_LINES);
         let text_cells = passes.storage_with_capacity("Tempestas Text Grid", TEXT_CELLS);
         let (placed_glyphs, glyphs, edges) = text.resources();
@@ -460,10 +465,10 @@ impl TempestasPass {
         }
     }
 
-    pub fn update(&mut self, text: &mut text::Renderer, status: Option<&mut status::StatusPass>, frame: &mut Frame) {
+    pub fn update(&mut self, text: &mut text::Renderer, status: Option<&mut status::StatusPass>, timers: &Timers, frame: &mut Frame) {
         let height = frame.config.height;
         let x = Self::pill_x(frame.shared.screen_size.x, frame.shared.status_width);
-        let (weather_label, hour) = self.collapsed_label(x, height, frame);
+        let (weather_label, timer_label, hour) = self.collapsed_label(x, height, timers, frame);
         frame.shared.weather_hour = hour;
         if let Some(status) = status {
             status.pill.sun_height = sun_position(hour, self.pill.sun_hours)[1];
@@ -483,7 +488,14 @@ impl TempestasPass {
             grid: text_grid(frame.shared, x),
             expansion: 1.0,
         };
-        labels.centered(&weather_label, WEATHER_STYLE, vec2(WIDTH * 0.5, height * 0.46), 1.0);
+        if let Some((timer_label, flash)) = timer_label {
+            // Timers take a line under the date, which shrinks to make room; a finished one pulses red.
+            labels.centered(&weather_label, WEATHER_STYLE.scaled(0.75), vec2(WIDTH * 0.5, height * 0.32), 1.0);
+            let pulse = flash * (0.5 + 0.5 * (frame.shared.time * 8.0).cos());
+            labels.colored(&timer_label, TIMER_STYLE, vec2(WIDTH * 0.5, height * 0.74), text::COLOR.lerp(ALERT_COLOR, pulse), 1.0);
+        } else {
+            labels.centered(&weather_label, WEATHER_STYLE, vec2(WIDTH * 0.5, height * 0.46), 1.0);
+        }
         self.calendar_labels(&mut labels, x, height, frame.delta_time, frame.interaction);
         self.text_lines.upload(&lines);
         self.text_cells.upload(&cells[..]);
@@ -491,7 +503,8 @@ impl TempestasPass {
         self.cells = cells;
     }
 
-    fn collapsed_label(&mut self, x: f32, height: f32, frame: &mut Frame) -> (ArrayString<64>, f32) {
+    /// The date line, the active timers line with how strongly it flashes, and the hour of day for the sky.
+    fn collapsed_label(&mut self, x: f32, height: f32, timers: &Timers, frame: &mut Frame) -> (ArrayString<64>, Option<(ArrayString<64>, f32)>, f32) {
         let hovered = Self::visible_rects(x, height, self.pill.calendar_expansion)
             .into_iter()
             .any(|rect| frame.interaction.contains(rect));
@@ -508,7 +521,26 @@ impl TempestasPass {
         } else {
             write!(label, "{}   {clock}", self.temperature).unwrap();
         }
-        (label, hour)
+
+        let now = time.timestamp();
+        let flash = timers.flash(now);
+        let mut timer_label = ArrayString::<64>::new();
+        if let Some((timer, _)) = flash {
+            let _ = write!(timer_label, "{} finished", timer.name());
+        } else {
+            for timer in &timers.active {
+                let mut reading = ArrayString::<40>::new();
+                if !timer_label.is_empty() {
+                    reading.push_str("  ·  ");
+                }
+                // Timers past the end of the line wait until earlier ones finish.
+                if timer.write_reading(now, &mut reading).is_err() || timer_label.try_push_str(&reading).is_err() {
+                    break;
+                }
+            }
+        }
+        let timer_label = (!timer_label.is_empty()).then_some((timer_label, flash.map_or(0.0, |(_, amount)| amount)));
+        (label, timer_label, hour)
     }
 
     fn calendar_labels(&mut self, text: &mut Labels<'_>, x: f32, height: f32, delta_time: f32, interaction: &mut InteractionState) {
@@ -719,20 +751,19 @@ impl TempestasPass {
 #[cfg(feature = "cpu")]
 mod monitor {
     use super::{ForecastItem, HOURLY_STEP_HOURS, ORDINALS, TempestasPass, WeatherCondition};
-    use crate::app::{AppUpdater, Background, send_update};
+    use crate::app::{AppUpdater, Background, config::Services, http::HttpClient, send_update};
     use futures_util::StreamExt;
     use jiff::{
         civil::DateTime,
         tz::{Offset, TimeZone},
     };
-    use reqwest::Client;
     use serde::{Deserialize, de::DeserializeOwned};
     use std::{array::from_fn, collections::HashMap, time::Duration};
     use tokio::{
         sync::mpsc::{self, UnboundedReceiver, UnboundedSender},
         time::{sleep, timeout},
     };
-    use tracing::warn;
+    use tracing::{info, warn};
     use zbus::{
         Connection, Proxy,
         proxy::Builder as ProxyBuilder,
@@ -834,7 +865,21 @@ mod monitor {
         99 => "Thunderstorm Heavy Hail" { rain: 0.85, lightning: 1.0, hail: 1.0 };
     }
 
-    pub(super) fn start(timezones: Vec<String>, background: &Background, updater: AppUpdater, http: Client) {
+    /// Open-Meteo base URLs; without geocoding only the location portal places the local weather.
+    struct Endpoints {
+        forecast: String,
+        geocoding: Option<String>,
+    }
+
+    pub(super) fn start(timezones: Vec<String>, services: &Services, background: &Background, updater: AppUpdater, http: HttpClient) {
+        let Some(forecast) = Services::endpoint(&services.open_meteo) else {
+            info!("Weather service disabled");
+            return;
+        };
+        let endpoints = Endpoints {
+            forecast,
+            geocoding: Services::endpoint(&services.open_meteo_geocoding),
+        };
         let (location_tx, locations) = mpsc::unbounded_channel();
         background.spawn(async move {
             if let Err(error) = stream_location(&location_tx).await {
@@ -843,7 +888,7 @@ mod monitor {
             None
         });
         background.spawn(async move {
-            refresh_loop(&http, &timezones, &updater, locations).await;
+            refresh_loop(&http, &endpoints, &timezones, &updater, locations).await;
             None
         });
     }
@@ -936,14 +981,17 @@ mod monitor {
         session.call::<_, _, ()>("Close", &()).await.map_err(|error| error.to_string())
     }
 
-    async fn refresh_loop(http: &Client, timezones: &[String], updater: &AppUpdater, mut locations_rx: UnboundedReceiver<[f32; 2]>) {
+    async fn refresh_loop(http: &HttpClient, endpoints: &Endpoints, timezones: &[String], updater: &AppUpdater, mut locations_rx: UnboundedReceiver<[f32; 2]>) {
         let mut locations = vec![None; timezones.len() + 1];
-        if let Some(timezone) = TimeZone::system().iana_name() {
-            match geocode(http, timezone).await {
+        let geocoding = endpoints.geocoding.as_deref();
+        if let Some(geocoding) = geocoding
+            && let Some(timezone) = TimeZone::system().iana_name()
+        {
+            match geocode(http, geocoding, timezone).await {
                 Ok(location) => locations[0] = Some(location),
                 Err(error) => warn!(%error, timezone, "Failed to locate system timezone"),
             }
-        } else {
+        } else if geocoding.is_some() {
             warn!("System timezone has no IANA name; waiting for the location portal");
         }
         loop {
@@ -953,8 +1001,11 @@ mod monitor {
             let mut retry = false;
             let mut ready = Vec::with_capacity(locations.len());
             for (index, location) in locations.iter_mut().enumerate() {
-                if index > 0 && location.is_none() {
-                    *location = geocode(http, &timezones[index - 1])
+                if index > 0
+                    && location.is_none()
+                    && let Some(geocoding) = geocoding
+                {
+                    *location = geocode(http, geocoding, &timezones[index - 1])
                         .await
                         .inspect_err(|error| {
                             retry = true;
@@ -963,12 +1014,13 @@ mod monitor {
                         .ok();
                 }
                 let Some([latitude, longitude]) = *location else {
-                    retry = true;
+                    // World clocks can only be placed again while geocoding is enabled.
+                    retry |= index == 0 || geocoding.is_some();
                     continue;
                 };
                 ready.push((index, [latitude, longitude]));
             }
-            let forecasts = match fetch(http, &ready).await {
+            let forecasts = match fetch(http, &endpoints.forecast, &ready).await {
                 Ok(results) => ready.into_iter().zip(results).map(|((index, _), forecast)| (index, forecast)).collect(),
                 Err(error) => {
                     retry = true;
@@ -1057,10 +1109,10 @@ mod monitor {
         );
     }
 
-    async fn geocode(http: &Client, timezone: &str) -> Result<[f32; 2], String> {
+    async fn geocode(http: &HttpClient, base: &str, timezone: &str) -> Result<[f32; 2], String> {
         let city = timezone.rsplit('/').next().unwrap_or(timezone).replace('_', " ");
         let query: String = form_urlencoded::byte_serialize(city.as_bytes()).collect();
-        let results: SearchResults = get_json(http, format!("https://geocoding-api.open-meteo.com/v1/search?name={query}&count=10")).await?;
+        let results: SearchResults = get_json(http, format!("{base}/search?name={query}&count=10")).await?;
         let place = results
             .results
             .iter()
@@ -1070,14 +1122,14 @@ mod monitor {
         Ok([place.latitude, place.longitude])
     }
 
-    async fn fetch(http: &Client, locations: &[(usize, [f32; 2])]) -> Result<Vec<Forecast>, String> {
+    async fn fetch(http: &HttpClient, base: &str, locations: &[(usize, [f32; 2])]) -> Result<Vec<Forecast>, String> {
         if locations.is_empty() {
             return Ok(Vec::new());
         }
         let latitude = locations.iter().map(|(_, [latitude, _])| latitude.to_string()).collect::<Vec<_>>().join(",");
         let longitude = locations.iter().map(|(_, [_, longitude])| longitude.to_string()).collect::<Vec<_>>().join(",");
         let url = format!(
-            "https://api.open-meteo.com/v1/forecast?latitude={latitude}&longitude={longitude}&current={WEATHER_FIELDS},relative_humidity_2m,wind_speed_10m&hourly={WEATHER_FIELDS}&forecast_hours=24&daily=weather_code,temperature_2m_max,temperature_2m_min,sunrise,sunset&temperature_unit=celsius&timezone=auto&forecast_days=6"
+            "{base}/forecast?latitude={latitude}&longitude={longitude}&current={WEATHER_FIELDS},relative_humidity_2m,wind_speed_10m&hourly={WEATHER_FIELDS}&forecast_hours=24&daily=weather_code,temperature_2m_max,temperature_2m_min,sunrise,sunset&temperature_unit=celsius&timezone=auto&forecast_days=6"
         );
         if locations.len() == 1 {
             get_json(http, url).await.map(|forecast| vec![forecast])
@@ -1086,14 +1138,11 @@ mod monitor {
         }
     }
 
-    async fn get_json<T: DeserializeOwned>(http: &Client, url: String) -> Result<T, String> {
+    async fn get_json<T: DeserializeOwned>(http: &HttpClient, url: String) -> Result<T, String> {
         http.get(url)
             .send()
             .await
-            .and_then(reqwest::Response::error_for_status)
-            .map_err(|error| error.to_string())?
-            .json()
-            .await
+            .and_then(|response| response.error_for_status()?.json())
             .map_err(|error| error.to_string())
     }
 }
@@ -12,13 +12,20 @@ use isthmus::spirv_std::num_traits::Float;
 
 #[cfg(feature = "cpu")]
 use {
-    crate::render::cpu::Passes,
+    crate::{
+        app::platform::{Current as Platform, Platform as _},
+        render::{cpu::Passes, launcher::CHARACTERS},
+    },
     isthmus::Storage,
     isthmus::glam::Vec4,
     std::{
+        collections::HashSet,
+        fs,
         ops::{Deref, Range},
+        path::PathBuf,
         sync::Arc,
     },
+    tracing::warn,
     ttf_parser::{Face, GlyphId, OutlineBuilder, Tag},
 };
 
@@ -236,6 +243,9 @@ const RANGES: &[(u32, u32)] = &[
     (0x20ac, 0x20ac),
     (0x266a, 0x266b),
 ];
+/// A fallback font must draw this to be used.
+#[cfg(feature = "cpu")]
+const FALLBACK_PROBE: char = '\u{1f600}';
 
 #[cfg(feature = "cpu")]
 #[derive(Clone, Copy)]
@@ -296,57 +306,34 @@ impl OutlineBuilder for Outline {
     }
 }
 
+/// Flattened outlines of every drawable character, across the embedded font and its fallback.
 #[cfg(feature = "cpu")]
 #[derive(Default)]
-pub struct ShapedLine {
-    glyphs: Vec<PlacedGlyph>,
-    min: Vec2,
-    max: Vec2,
-    pub(crate) width: f32,
-    baseline: f32,
-    size: f32,
-    weight: f32,
-}
-
-#[cfg(feature = "cpu")]
-#[derive(Clone)]
-pub struct Shaper {
-    characters: Arc<[(char, Meta)]>,
-    baseline: f32,
-}
-
-#[cfg(feature = "cpu")]
-pub struct Renderer {
-    shaper: Shaper,
-    edges: Storage<Edge>,
-    glyphs: Storage<Glyph>,
-    storage: Storage<PlacedGlyph>,
-    placed: Vec<PlacedGlyph>,
+struct GlyphSet {
+    edges: Vec<Edge>,
+    glyphs: Vec<Glyph>,
+    characters: Vec<(char, Meta)>,
 }
 
 #[cfg(feature = "cpu")]
-impl Renderer {
-    /// Creates the shared vector-font renderer and GPU storage.
-    ///
-    /// # Panics
-    ///
-    /// Panics if Cantus's embedded font cannot be parsed or its outlines cannot be read.
-    pub fn new(passes: &Passes<'_>) -> Self {
-        let mut face = Face::parse(FONT, 0).expect("parse variable font");
-        let characters = RANGES
-            .iter()
-            .flat_map(|&(a, b)| a..=b)
-            .filter_map(char::from_u32)
+impl GlyphSet {
+    /// Adds the glyphs `face` has for `characters`, sized in its own ems. A variable face is
+    /// outlined at both ends of its weight axis; a static one is outlined once and used for both.
+    fn add_face(&mut self, face: &mut Face<'_>, variable: bool, characters: impl IntoIterator<Item = char>) {
+        let characters = characters
+            .into_iter()
             .filter_map(|character| face.glyph_index(character).map(|id| (character, id.0)))
             .collect::<Vec<_>>();
         let mut ids = characters.iter().map(|&(_, id)| id).collect::<Vec<_>>();
         ids.sort_unstable();
         ids.dedup();
-        let span = f32::from(face.ascender() - face.descender());
-        let baseline = f32::from(face.ascender() + face.descender()) * 0.5 / span;
+        let span = em_span(face);
+        let weights: &[f32] = if variable { &[600.0, 900.0] } else { &[600.0] };
         let mut outlines = Vec::new();
-        for weight in [600.0, 900.0] {
-            face.set_variation(WGHT, weight).expect("font must have a weight axis");
+        for &weight in weights {
+            if variable {
+                face.set_variation(WGHT, weight).expect("font must have a weight axis");
+            }
             outlines.push(
                 ids.iter()
                     .map(|&id| {
@@ -366,12 +353,12 @@ impl Renderer {
                     .collect::<Vec<_>>(),
             );
         }
-        let (mut curves, mut metadata) = (Vec::new(), Vec::new());
+        let mut metadata = Vec::new();
         for (index, id) in ids.iter().copied().enumerate() {
             let (low, low_advance, low_bounds) = &outlines[0][index];
-            let (high, high_advance, high_bounds) = &outlines[1][index];
+            let (high, high_advance, high_bounds) = &outlines[outlines.len() - 1][index];
             assert_eq!(low.len(), high.len(), "variable outline topology changed");
-            let start = curves.len() as u32;
+            let start = self.edges.len() as u32;
             for (&[a, b, c], &[d, e, f]) in low.iter().zip(high) {
                 // Flatten once on the CPU. The tolerance is in ems and keeps the
                 // largest text comfortably below a quarter pixel of curve error.
@@ -388,7 +375,7 @@ impl Renderer {
                     let low_end = point(a, b, c, t1);
                     let high_start = point(d, e, f, t0);
                     let high_end = point(d, e, f, t1);
-                    curves.push(Edge {
+                    self.edges.push(Edge {
                         start: low_start,
                         end: low_end,
                         start_delta: high_start - low_start,
@@ -396,30 +383,113 @@ impl Renderer {
                     });
                 }
             }
-            let count = curves.len() - start as usize;
+            let data = Glyph {
+                start,
+                count: self.edges.len() as u32 - start,
+                min: low_bounds.0.min(high_bounds.0),
+                max: low_bounds.1.max(high_bounds.1),
+            };
             metadata.push((
                 id,
                 Meta {
-                    glyph: index as u32,
-                    data: Glyph {
-                        start,
-                        count: count as u32,
-                        min: low_bounds.0.min(high_bounds.0),
-                        max: low_bounds.1.max(high_bounds.1),
-                    },
+                    glyph: self.glyphs.len() as u32,
+                    data,
                     advance: [*low_advance, *high_advance],
                 },
             ));
+            self.glyphs.push(data);
         }
-        let characters = characters
-            .into_iter()
-            .filter_map(|(character, id)| metadata.binary_search_by_key(&id, |&(id, _)| id).ok().map(|index| (character, metadata[index].1)))
-            .collect::<Vec<_>>();
-        let edges = passes.storage("Vector Font Edges", curves);
-        let glyphs = passes.storage("Vector Font Glyphs", metadata.iter().map(|(_, meta)| meta.data));
+        self.characters.extend(
+            characters
+                .into_iter()
+                .filter_map(|(character, id)| metadata.binary_search_by_key(&id, |&(id, _)| id).ok().map(|index| (character, metadata[index].1))),
+        );
+    }
+}
+
+/// Height from descender to ascender, which every outline is divided by.
+#[cfg(feature = "cpu")]
+fn em_span(face: &Face<'_>) -> f32 {
+    f32::from(face.ascender() - face.descender())
+}
+
+/// Reads the configured fallback font, or else the first system font drawing emoji as outlines;
+/// colour bitmap fonts have none and are skipped.
+#[cfg(feature = "cpu")]
+fn load_fallback(configured: Option<&str>) -> Option<Vec<u8>> {
+    let candidates = configured.map_or_else(Platform::emoji_fonts, |path| vec![PathBuf::from(path)]);
+    let found = candidates.into_iter().find_map(|path| {
+        let data = fs::read(&path).ok()?;
+        let face = Face::parse(&data, 0).ok()?;
+        let probe = face.glyph_index(FALLBACK_PROBE)?;
+        face.outline_glyph(probe, &mut Outline::default())?;
+        Some(data)
+    });
+    if found.is_none() {
+        warn!(?configured, "No outline emoji font found; emoji won't be drawn");
+    }
+    found
+}
+
+#[cfg(feature = "cpu")]
+#[derive(Default)]
+pub struct ShapedLine {
+    glyphs: Vec<PlacedGlyph>,
+    min: Vec2,
+    max: Vec2,
+    pub(crate) width: f32,
+    baseline: f32,
+    size: f32,
+    weight: f32,
+}
+
+#[cfg(feature = "cpu")]
+#[derive(Clone)]
+pub struct Shaper {
+    characters: Arc<[(char, Meta)]>,
+    baseline: f32,
+}
+
+#[cfg(feature = "cpu")]
+pub struct Renderer {
+    shaper: Shaper,
+    edges: Storage<Edge>,
+    glyphs: Storage<Glyph>,
+    storage: Storage<PlacedGlyph>,
+    placed: Vec<PlacedGlyph>,
+}
+
+#[cfg(feature = "cpu")]
+impl Renderer {
+    /// Creates the shared vector-font renderer and GPU storage. Characters the embedded font
+    /// lacks are drawn from `fallback_font`, or a system emoji font when that is unset.
+    ///
+    /// # Panics
+    ///
+    /// Panics if Cantus's embedded font cannot be parsed or its outlines cannot be read.
+    pub fn new(passes: &Passes<'_>, fallback_font: Option<&str>) -> Self {
+        let mut face = Face::parse(FONT, 0).expect("parse variable font");
+        let baseline = f32::from(face.ascender() + face.descender()) * 0.5 / em_span(&face);
+        let mut set = GlyphSet::default();
+        set.add_face(&mut face, true, RANGES.iter().flat_map(|&(a, b)| a..=b).filter_map(char::from_u32));
+        let covered = set.characters.iter().map(|&(character, _)| character).collect::<HashSet<_>>();
+        if let Some(data) = load_fallback(fallback_font)
+            && let Ok(mut fallback) = Face::parse(&data, 0)
+        {
+            // Only the characters the picker offers are outlined, rather than whole symbol and emoji blocks.
+            let missing = CHARACTERS
+                .lines()
+                .filter_map(|line| line.chars().next())
+                .filter(|character| !covered.contains(character))
+                .collect::<HashSet<_>>();
+            set.add_face(&mut fallback, false, missing);
+        }
+        set.characters.sort_unstable_by_key(|&(character, _)| character);
+        let edges = passes.storage("Vector Font Edges", set.edges);
+        let glyphs = passes.storage("Vector Font Glyphs", set.glyphs);
         Self {
             shaper: Shaper {
-                characters: characters.into(),
+                characters: set.characters.into(),
                 baseline,
             },
             edges,
@@ -14,18 +14,22 @@ use isthmus::{
 use isthmus::spirv_std::num_traits::Float;
 
 #[cfg(feature = "cpu")]
-use crate::{
-    app::{
-        interaction::Rect,
-        music::{AlbumArt, IMAGE_SIZE, TRACK_SPACING_MS},
-        music::{CondensedPlaylist, PlaybackState, Timeline, Track, playlist_icons},
-    },
-    render::{
-        GAP,
-        atlas::TextureAtlas,
-        cpu::{Frame, Passes, approach},
-        text::TextStyle,
+use {
+    crate::{
+        app::{
+            interaction::{Rect, Response},
+            music::{AlbumArt, ArtState, Enrichment, IMAGE_SIZE, MAX_LARGE_IMAGE_SIZE, PlayerStream, TRACK_SPACING_MS, step_volume},
+            music::{CondensedPlaylist, PlaybackState, Timeline, Track, playlist_icons},
+            platform::{Current as Platform, Platform as _},
+        },
+        render::{
+            GAP,
+            atlas::TextureAtlas,
+            cpu::{Frame, Passes, approach},
+            text::TextStyle,
+        },
     },
+    std::{fmt::Write, time::Instant},
 };
 
 /// Maximum number of playlist artwork icons carried by one pill instance.
@@ -43,23 +47,33 @@ mod host {
     use super::TextStyle;
 
     pub const MAX_TEXTURE_IMAGES: u32 = 32;
+    /// Hovered and expanded pills, which alone show large art, rarely number more than a couple.
+    pub const MAX_LARGE_IMAGES: u32 = 4;
+    /// Large art is fetched in steps of this many pixels, so small layout changes reuse it.
+    pub const LARGE_IMAGE_STEP: u32 = 32;
+    /// Most the hover bulge grows a pill's art, in logical pixels.
+    pub const ART_HOVER_GROWTH: f32 = 8.0;
     pub const MAX_RENDER_INSTANCES: usize = 32;
     pub const TITLE_STYLE: TextStyle = TextStyle::new(16.0, 700.0);
     pub const DETAILS_STYLE: TextStyle = TextStyle::new(14.0, 700.0);
     pub const DETAIL_FADE_DURATION: f32 = 0.2;
     pub const PLAYLIST_EXPANSION_DURATION: f32 = 1.0 / 6.0;
+    pub const INTERRUPTION_DURATION: f32 = 0.6;
 }
 
 #[cfg(feature = "cpu")]
-use host::{DETAIL_FADE_DURATION, DETAILS_STYLE, MAX_RENDER_INSTANCES, MAX_TEXTURE_IMAGES, PLAYLIST_EXPANSION_DURATION, TITLE_STYLE};
+use host::{
+    ART_HOVER_GROWTH, DETAIL_FADE_DURATION, DETAILS_STYLE, INTERRUPTION_DURATION, LARGE_IMAGE_STEP, MAX_LARGE_IMAGES, MAX_RENDER_INSTANCES, MAX_TEXTURE_IMAGES,
+    PLAYLIST_EXPANSION_DURATION, TITLE_STYLE,
+};
 
 #[cfg(feature = "cpu")]
 impl TextureAtlas {
-    fn track_index(&mut self, url: Option<&str>, art: Option<&AlbumArt>) -> i32 {
+    fn track_index(&mut self, url: Option<&str>, art: Option<&AlbumArt>, size: u32) -> i32 {
         let (Some(url), Some(art)) = (url, art) else {
             return -1;
         };
-        self.index_of(url, [IMAGE_SIZE; 2], &art.pixels).map_or(-1, |index| index as i32)
+        self.index_of(url, [size; 2], &art.pixels).map_or(-1, |index| index as i32)
     }
 }
 
@@ -67,7 +81,11 @@ impl TextureAtlas {
 pub struct TrackPass {
     pub(crate) instances: isthmus::Instances<Self>,
     images: TextureAtlas,
+    large_images: TextureAtlas,
+    enrichment: Enrichment,
     pub current_track_palette: Option<[Unorm8x4; PALETTE_COLORS]>,
+    /// Left edge and width of the playing track's pill.
+    pub current_track_span: Option<(f32, f32)>,
 }
 
 #[derive(isthmus::Varyings)]
@@ -84,6 +102,10 @@ pub struct TrackPill {
     pub width: f32,
     pub colors: [Unorm8x4; PALETTE_COLORS],
     pub image_index: i32,
+    /// Layer in the large art atlas, or -1 to use `image_index`.
+    pub large_image_index: i32,
+    /// Share of the large art layer the art fills, from its top-left corner.
+    pub large_image_extent: f32,
     pub rating: i32,
     pub primary_playlist_count: u32,
     pub secondary_playlist_count: u32,
@@ -92,6 +114,8 @@ pub struct TrackPill {
     pub secondary_expansion: f32,
     pub seed: f32,
     pub effects: AudioFeatures,
+    /// 1 for the playing track, whose pulse follows the detected beat.
+    pub live_beat: f32,
     pub playlist_images: [i32; MAX_PILL_PLAYLIST_ICONS],
     pub lines: [text::Line; 2],
 }
@@ -121,7 +145,7 @@ impl TrackPill {
 /// Per-track character, every field normalised to 0..1 on arrival.
 #[isthmus::data]
 #[derive(Default)]
-#[cfg_attr(feature = "cpu", derive(serde::Deserialize))]
+#[cfg_attr(feature = "cpu", derive(serde::Deserialize, serde::Serialize))]
 pub struct AudioFeatures {
     pub energy: f32,
     pub danceability: f32,
@@ -138,10 +162,12 @@ impl AudioFeatures {
         self.energy * 0.55 + self.danceability * 0.25 + self.loudness * 0.2
     }
 
-    /// Beat pulse, sharpened so it reads as a kick rather than a sine.
-    fn beat(self, time: f32) -> f32 {
-        let pulse = (time * self.tempo * 5.0 * TAU).sin() * 0.5 + 0.5;
-        pulse * pulse * self.danceability * (0.025 + self.energy * 0.055)
+    /// Beat pulse, sharpened so it reads as a kick rather than a sine. Runs free at the track's
+    /// tempo, locking onto the detected beat as far as `live` and the beat's confidence allow.
+    fn beat(self, frame: FrameData, live: f32) -> f32 {
+        let pulse = (frame.time * self.tempo * 5.0 * TAU).sin() * 0.5 + 0.5;
+        let pulse = (pulse * pulse).lerp(frame.beat_envelope(), live * frame.beat_strength);
+        pulse * self.danceability * (0.025 + self.energy * 0.055)
     }
 
     /// Time scaled so energetic, fast tracks flow faster, offset per track by `seed`.
@@ -283,18 +309,23 @@ fn caustics(p: Vec2, time: f32, seed: f32, effects: AudioFeatures) -> f32 {
 
 #[isthmus::pass]
 impl TrackPass {
-    pub fn new(passes: &Passes<'_>, text: &text::Renderer) -> Self {
+    pub fn new(passes: &Passes<'_>, text: &text::Renderer, enrichment: Enrichment) -> Self {
         let images = TextureAtlas::new(passes, "Images", [IMAGE_SIZE; 2], MAX_TEXTURE_IMAGES as usize);
+        let large_images = TextureAtlas::new(passes, "Large Images", [MAX_LARGE_IMAGE_SIZE; 2], MAX_LARGE_IMAGES as usize);
         let sampler = passes.filtering_sampler("Linear Sampler");
         let (placed_glyphs, glyphs, edges) = text.resources();
         Self {
-            instances: passes.instances((images.view(), &sampler, placed_glyphs, glyphs, edges), []),
+            instances: passes.instances((images.view(), large_images.view(), &sampler, placed_glyphs, glyphs, edges), []),
             images,
+            large_images,
+            enrichment,
             current_track_palette: None,
+            current_track_span: None,
         }
     }
 
-    fn track_details(track: &Track, start_ms: f32) -> String {
+    /// Time to or since the track and its artist, followed on hover by its release year, genres and label.
+    fn track_details(track: &Track, start_ms: f32, expanded: bool) -> String {
         let seconds = (start_ms / 1000.0).abs();
         let time = if seconds >= 60.0 {
             let seconds = seconds as u32;
@@ -303,7 +334,33 @@ impl TrackPass {
             format!("{}s", seconds.round())
         };
         let artist = &track.artist;
-        format!("{time}\u{2004}•\u{2004}{artist}")
+        let mut details = format!("{time}\u{2004}•\u{2004}{artist}");
+        if expanded && let Some(release) = track.runtime.release.ready() {
+            let genres = (!release.genres.is_empty()).then(|| release.genres[..release.genres.len().min(2)].join(", "));
+            for detail in release.year.map(|year| year.to_string()).into_iter().chain(genres).chain(release.label.clone()) {
+                let _ = write!(details, "\u{2004}•\u{2004}{detail}");
+            }
+        }
+        details
+    }
+
+    /// Layer and filled share of `track`'s art in the large atlas, fetching it at the `pixels` it is drawn at
+    /// when that is more than the small copy has, and showing the small one meanwhile.
+    fn large_art(&mut self, track: &mut Track, pixels: f32) -> (i32, f32) {
+        let Some(url) = track.image.as_deref().filter(|_| pixels > IMAGE_SIZE as f32) else {
+            return (-1, 1.0);
+        };
+        let size = (pixels.ceil() as u32).next_multiple_of(LARGE_IMAGE_STEP).min(MAX_LARGE_IMAGE_SIZE);
+        // A change of output scale wants the art at another size.
+        if track.runtime.large_art.ready().is_some_and(|art| art.pixels.len() != (size * size * 4) as usize) {
+            track.runtime.large_art = ArtState::default();
+        }
+        let now = Instant::now();
+        if self.enrichment.health.available(url, now) && track.runtime.large_art.request(now) {
+            self.enrichment.request_large_art(url, size);
+        }
+        let index = self.large_images.track_index(Some(format!("{url}@{size}").as_str()), track.runtime.large_art.ready(), size);
+        (index, size as f32 / MAX_LARGE_IMAGE_SIZE as f32)
     }
 
     fn prepare_pill(
@@ -313,9 +370,10 @@ impl TrackPass {
         layout: &mut TrackLayout,
         playlists: &mut [CondensedPlaylist],
         timeline: &Timeline,
+        player: &mut Option<PlayerStream>,
         frame: &mut Frame,
-        pill_queue_index: usize,
-    ) -> (TrackPill, bool) {
+        pill_queue_index: Option<usize>,
+    ) -> (TrackPill, Response) {
         let height = frame.config.height;
         // Bare song name, without remix or feature suffixes.
         let title = track.name.split_once(" -").map_or(track.name.as_str(), |(name, _)| name);
@@ -323,7 +381,7 @@ impl TrackPass {
         let title = if title.is_empty() { track.name.trim() } else { title };
         let playlist_expansion = smoothstep(0.0, 1.0, track.runtime.playlist_expansion);
         let labels = (layout.width > height + 26.0 || playlist_expansion > 0.0).then(|| {
-            let details = Self::track_details(track, layout.start_ms);
+            let details = Self::track_details(track, layout.start_ms, playlist_expansion > 0.0);
             (text.shape(title, TITLE_STYLE), text.shape(&details, DETAILS_STYLE))
         });
         if playlist_expansion > 0.0
@@ -361,7 +419,7 @@ impl TrackPass {
                     .filter(|_| track.runtime.playlist_expansion > 0.0),
             );
             for (slot, (image, (primary, playlist))) in playlist_images.iter_mut().zip(icons).enumerate() {
-                *image = self.images.track_index(playlist.image_url.as_deref(), playlist.art.ready());
+                *image = self.images.track_index(playlist.image_url.as_deref(), playlist.art.ready(), IMAGE_SIZE);
                 playlist_ids[slot] = Some(playlist.id);
                 primary_count += u32::from(primary);
                 secondary_count += u32::from(!primary);
@@ -393,7 +451,9 @@ impl TrackPass {
             x: layout.x,
             width: layout.width.max(height),
             colors: track.runtime.art.palette(),
-            image_index: self.images.track_index(track.image.as_deref(), track.runtime.art.ready()),
+            image_index: self.images.track_index(track.image.as_deref(), track.runtime.art.ready(), IMAGE_SIZE),
+            large_image_index: -1,
+            large_image_extent: 1.0,
             rating,
             primary_playlist_count: primary_count,
             secondary_playlist_count: secondary_count,
@@ -435,12 +495,16 @@ impl TrackPass {
             }
         }
 
-        let body = frame.interaction.surface(Rect::pill(pill.x, pill.width, frame.config.height));
+        let body_rect = Rect::pill(pill.x, pill.width, frame.config.height);
+        let body = frame.interaction.surface(body_rect);
         hovered |= body.hovered;
         if body.pressed {
             frame.interaction.enable_drag();
         }
-        if body.clicked && track.id.is_some() {
+        if body.clicked
+            && track.id.is_some()
+            && let Some(pill_queue_index) = pill_queue_index
+        {
             let natural_start = frame.shared.playhead_x + layout.start_ms * frame.shared.px_per_ms;
             let fraction = if frame.interaction.pointer.x < frame.config.history_width + 40.0 {
                 0.0
@@ -449,18 +513,47 @@ impl TrackPass {
             };
             frame.interaction.seek(timeline, pill_queue_index, track.duration_ms, fraction);
         }
+        // Scrolling or middle-clicking any track adjusts only the player's stream, not the whole sink.
+        if let Some(stream) = player {
+            let scroll = frame.interaction.scroll(body_rect);
+            if scroll != 0 {
+                stream.volume = step_volume(stream.volume, scroll);
+                Platform::set_volume(Some(stream.node), stream.volume.abs());
+            }
+            if frame.interaction.middle_clicked(body_rect) {
+                stream.volume = -stream.volume;
+                Platform::set_muted(Some(stream.node), stream.volume.is_sign_negative());
+            }
+        }
+        if hovered || track.runtime.playlist_expansion > 0.0 {
+            (pill.large_image_index, pill.large_image_extent) = self.large_art(track, (height + ART_HOVER_GROWTH) * frame.scale);
+        }
         approach(
             &mut track.runtime.playlist_expansion,
             f32::from(hovered && show_details && detail_alpha >= 1.0),
             frame.delta_time.min(0.1) / PLAYLIST_EXPANSION_DURATION,
         );
-        (pill, hovered)
+        let response = Response {
+            hovered,
+            pressed: body.pressed,
+            clicked: body.clicked,
+        };
+        (pill, response)
     }
 
     pub fn update(&mut self, text: &mut text::Renderer, playback: &mut PlaybackState, frame: &mut Frame) {
         self.images.begin_frame();
-        if playback.queue.is_empty() {
+        self.large_images.begin_frame();
+        if playback
+            .interruption
+            .as_ref()
+            .is_some_and(|interruption| !interruption.open && interruption.presence <= 0.0)
+        {
+            playback.interruption = None;
+        }
+        if playback.queue.is_empty() && playback.interruption.is_none() {
             self.current_track_palette = None;
+            self.current_track_span = None;
             self.instances.clear();
             return;
         }
@@ -479,6 +572,50 @@ impl TrackPass {
         let width_trim = (GAP - gap).max(0.0);
         let end_x = frame.config.history_width + frame.config.timeline_future_minutes * 60_000.0 * px_per_ms + width_trim;
         let (history_width, panel_height) = (frame.config.history_width, frame.config.height);
+        // Another player's media opens a gap at the playhead: the track it interrupted resumes after it, earlier ones move before it.
+        let mut interruption_pill = None;
+        let (mut past_shift_ms, mut future_shift_ms, mut resume_index) = (0.0, 0.0, playback.queue.len());
+        if let Some(interruption) = &mut playback.interruption {
+            approach(&mut interruption.presence, f32::from(interruption.open), frame.delta_time / INTERRUPTION_DURATION);
+            let presence = smoothstep(0.0, 1.0, interruption.presence);
+            let (played_ms, remaining_ms) = interruption.extent_ms();
+            let mut resume_ms = current_ms;
+            resume_index = playback
+                .queue
+                .iter()
+                .position(|track| {
+                    let unfinished = resume_ms + track.duration_ms as f32 > 0.0;
+                    if !unfinished {
+                        resume_ms += track.queue_span_ms();
+                    }
+                    unfinished
+                })
+                .unwrap_or(playback.queue.len());
+            past_shift_ms = -(played_ms + resume_ms) * presence;
+            future_shift_ms = (remaining_ms + TRACK_SPACING_MS - resume_ms) * presence;
+
+            let x = (playhead_x - played_ms * presence * px_per_ms).max(history_width);
+            let mut layout = TrackLayout {
+                start_ms: -played_ms,
+                x,
+                width: (playhead_x + remaining_ms * presence * px_per_ms).min(end_x) - x,
+            };
+            let (mut pill, response) = self.prepare_pill(
+                text,
+                &mut interruption.track,
+                &mut layout,
+                &mut playback.playlists,
+                &playback.timeline,
+                &mut None,
+                frame,
+                None,
+            );
+            pill.visibility *= presence;
+            if response.clicked {
+                interruption.toggle(&self.enrichment.background);
+            }
+            interruption_pill = Some(pill);
+        }
         // Walked newest first, so compact history pills stack leftward from the playhead.
         let mut compact_slot = 0;
         let mut transition = 0.0;
@@ -486,10 +623,11 @@ impl TrackPass {
         for pill_queue_index in (0..playback.queue.len()).rev() {
             let track = &mut playback.queue[pill_queue_index];
             queue_end_ms -= track.queue_span_ms();
-            let natural_start = playhead_x + queue_end_ms * px_per_ms;
+            let start_ms = queue_end_ms + if pill_queue_index < resume_index { past_shift_ms } else { future_shift_ms };
+            let natural_start = playhead_x + start_ms * px_per_ms;
             let natural_end = natural_start + track.duration_ms as f32 * px_per_ms;
             let mut layout = TrackLayout {
-                start_ms: queue_end_ms,
+                start_ms,
                 x: 0.0,
                 width: panel_height,
             };
@@ -506,10 +644,21 @@ impl TrackPass {
                 compact_slot += 1;
                 layout.x = right - panel_height;
             }
-            let can_render = self.instances.len() + usize::from(foreground.is_some()) < MAX_RENDER_INSTANCES;
+            let reserved = usize::from(foreground.is_some()) + usize::from(interruption_pill.is_some());
+            let can_render = self.instances.len() + reserved < MAX_RENDER_INSTANCES;
             if can_render && layout.width > 0.0 && layout.x + layout.width > 0.0 {
-                let (pill, hovered) = self.prepare_pill(text, track, &mut layout, &mut playback.playlists, &playback.timeline, frame, pill_queue_index);
-                if hovered {
+                let (mut pill, response) = self.prepare_pill(
+                    text,
+                    track,
+                    &mut layout,
+                    &mut playback.playlists,
+                    &playback.timeline,
+                    &mut playback.mixer.player,
+                    frame,
+                    Some(pill_queue_index),
+                );
+                pill.live_beat = f32::from(current_index == Some(pill_queue_index));
+                if response.hovered {
                     foreground = Some(pill);
                 } else {
                     self.instances.push(pill);
@@ -520,6 +669,9 @@ impl TrackPass {
             }
         }
         self.instances.reverse();
+        if let Some(pill) = interruption_pill {
+            self.instances.push(pill);
+        }
         if let Some(pill) = foreground {
             self.instances.push(pill);
         }
@@ -536,6 +688,7 @@ impl TrackPass {
             frame.interaction.cancel_drag();
         }
         self.current_track_palette = current_track.map(|(index, _)| playback.queue[index].runtime.art.palette());
+        self.current_track_span = current_track.map(|(_, layout)| (layout.x, layout.width));
     }
 
     #[gpu]
@@ -573,6 +726,7 @@ impl TrackPass {
         #[gpu(shared)] frame: FrameData,
         #[gpu(instance = pill_idx as usize)] pill: TrackPill,
         #[gpu(resource)] images: &Texture2DArray,
+        #[gpu(resource)] large_images: &Texture2DArray,
         #[gpu(resource)] sampler: &Sampler,
         #[gpu(resource)] placed_glyphs: &[text::PlacedGlyph],
         #[gpu(resource)] glyphs: &[text::Glyph],
@@ -601,7 +755,7 @@ impl TrackPass {
 
         // Weight overlapping wave fields by each palette colour's prevalence.
         let flow_time = pill.effects.flow_time(frame.time, pill.seed);
-        let beat = pill.effects.beat(frame.time);
+        let beat = pill.effects.beat(frame, pill.live_beat);
         let turbulence = pill.effects.turbulence();
         let lens_warp = (1.0 + dist.min(0.0) / 120.0).saturate();
         let deformation = local_centered * lens_warp * lens_warp * 0.6 + interaction.ripple;
@@ -640,7 +794,11 @@ impl TrackPass {
             let radius = pill_size.y * 0.5 + interaction.bulge(surface) * 0.5;
             let image_dist = offset.length() - radius;
             let uv_img = offset / (radius * 2.0) + 0.5;
-            let tex = images.sample(*sampler, uv_img.extend(pill.image_index as f32));
+            let tex = if pill.large_image_index >= 0 {
+                large_images.sample(*sampler, (uv_img * pill.large_image_extent).extend(pill.large_image_index as f32))
+            } else {
+                images.sample(*sampler, uv_img.extend(pill.image_index as f32))
+            };
             let img_mask = (1.0 - smoothstep(-4.0, 0.0, image_dist)) * (1.0 - smoothstep(-0.5, 0.5, interaction.expand(body_surface)));
             color = color.lerp(tex.truncate(), img_mask * tex.w);
         }
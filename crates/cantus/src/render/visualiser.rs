@@ -0,0 +1,166 @@
+use crate::render::{
+    FrameData, UNIT,
+    shader::{fill, pixel_to_ndc, quad_coord, sd_capsule_box},
+    smoothstep,
+    track::PALETTE_COLORS,
+};
+use isthmus::{
+    Unorm8x4, Vertex,
+    glam::{Vec2, Vec3, Vec4, vec2},
+    spirv_std::arch::kill,
+};
+
+#[cfg(target_arch = "spirv")]
+use isthmus::spirv_std::num_traits::Float;
+
+#[cfg(feature = "cpu")]
+use {
+    crate::{
+        app::{config::Visualiser, music::AudioTap},
+        render::{
+            PANEL_START,
+            cpu::{Frame, Passes, approach},
+            track::TrackPass,
+        },
+    },
+    std::sync::{Arc, atomic::Ordering},
+};
+
+pub const MAX_BANDS: usize = 128;
+pub const WAVEFORM_POINTS: usize = 128;
+/// Height of the full-width visualiser along the lyrics strip.
+pub const STRIP_HEIGHT: f32 = UNIT * 5.0;
+const BAR_GAP: f32 = 1.5;
+const EDGE_FADE: f32 = 32.0;
+
+/// A detailed log-frequency spectrum of the playing audio, tinted from the current track's palette.
+#[isthmus::pass]
+pub struct VisualiserPass {
+    view: isthmus::Instance<Self>,
+    audio: Arc<AudioTap>,
+    pub placement: Visualiser,
+}
+
+#[isthmus::data]
+pub struct VisualiserView {
+    pub origin: Vec2,
+    pub size: Vec2,
+    /// 1 to clip to the track pill's capsule, 0 for the strip's faded edges.
+    pub capsule: f32,
+    pub alpha: f32,
+    pub band_count: u32,
+    pub point_count: u32,
+    pub colors: [Unorm8x4; PALETTE_COLORS],
+    pub bands: [f32; MAX_BANDS],
+    pub waveform: [f32; WAVEFORM_POINTS],
+}
+
+#[derive(isthmus::Varyings)]
+pub struct Varyings {
+    pub pixel: Vec2,
+}
+
+/// The palette blended smoothly across `t` in 0..1.
+fn palette_gradient(colors: &[Unorm8x4; PALETTE_COLORS], t: f32) -> Vec3 {
+    let position = t.clamp(0.0, 1.0) * (PALETTE_COLORS - 1) as f32;
+    let index = (position.floor() as usize).min(PALETTE_COLORS - 2);
+    colors[index].to_vec3().lerp(colors[index + 1].to_vec3(), position - index as f32)
+}
+
+#[isthmus::pass]
+impl VisualiserPass {
+    pub fn new(passes: &Passes<'_>, audio: Arc<AudioTap>, placement: Visualiser) -> Self {
+        Self {
+            view: passes.instance(
+                (),
+                VisualiserView {
+                    origin: Vec2::ZERO,
+                    size: Vec2::ZERO,
+                    capsule: f32::from(placement == Visualiser::Pill),
+                    alpha: 0.0,
+                    band_count: audio.detail.len() as u32,
+                    point_count: audio.waveform.len() as u32,
+                    colors: [Unorm8x4::default(); PALETTE_COLORS],
+                    bands: [0.0; MAX_BANDS],
+                    waveform: [0.0; WAVEFORM_POINTS],
+                },
+            ),
+            audio,
+            placement,
+        }
+    }
+
+    pub fn update(&mut self, track: &TrackPass, frame: &Frame<'_>) {
+        let view = &mut *self.view;
+        let height = frame.config.height;
+        let bounds = match self.placement {
+            Visualiser::Pill => track.current_track_span.map(|(x, width)| (vec2(x, PANEL_START), vec2(width, height))),
+            _ => Some((vec2(0.0, PANEL_START + height), vec2(frame.shared.screen_size.x, STRIP_HEIGHT))),
+        };
+        let shown = bounds.is_some() && track.current_track_palette.is_some();
+        if let Some((origin, size)) = bounds {
+            (view.origin, view.size) = (origin, size);
+        }
+        if let Some(palette) = track.current_track_palette {
+            view.colors = palette;
+        }
+        approach(&mut view.alpha, f32::from(shown), frame.delta_time * 4.0);
+
+        let settle = 1.0 - (-frame.delta_time / frame.config.visualiser_smoothing.max(0.001)).exp();
+        let levels = view.bands.iter_mut().zip(&self.audio.detail);
+        for (damped, level) in levels.chain(view.waveform.iter_mut().zip(&self.audio.waveform)) {
+            *damped += (f32::from_bits(level.load(Ordering::Relaxed)) - *damped) * settle;
+        }
+    }
+
+    #[gpu]
+    pub fn vertex(#[gpu(vertex_index)] vertex: u32, #[gpu(shared)] frame: FrameData, #[gpu(instance)] view: VisualiserView) -> Vertex<Varyings> {
+        let pixel = view.origin + quad_coord(vertex) * view.size;
+        Vertex {
+            position: pixel_to_ndc(pixel, frame.screen_size),
+            varyings: Varyings { pixel },
+        }
+    }
+
+    #[gpu]
+    pub fn fragment(Varyings { pixel }: Varyings, #[gpu(shared)] frame: FrameData, #[gpu(instance)] view: VisualiserView) -> Vec4 {
+        if frame.launcher_open > 0.5 || view.alpha <= 0.0 || view.band_count == 0 {
+            kill();
+        }
+        let local = pixel - view.origin;
+        let uv = local / view.size;
+
+        // Bars rise from the bottom edge, one per band, split by a small gap.
+        let bands = view.band_count as f32;
+        let position = uv.x * bands;
+        let band = (position.floor() as u32).min(view.band_count - 1);
+        let bar_width = view.size.x / bands;
+        let from_center = (position.fract() - 0.5).abs() * bar_width;
+        let bar_top = view.size.y * (1.0 - view.bands[band as usize]);
+        let bar = fill(from_center - (bar_width * 0.5 - BAR_GAP * 0.5)) * smoothstep(bar_top - 0.5, bar_top + 0.5, local.y);
+        let rise = 1.0 - uv.y;
+        let mut color = palette_gradient(&view.colors, uv.x).lerp(Vec3::ONE, rise * 0.35);
+        let mut coverage = bar * (0.45 + rise * 0.55);
+
+        if view.point_count > 1 {
+            let position = uv.x * (view.point_count - 1) as f32;
+            let index = (position.floor() as u32).min(view.point_count - 2) as usize;
+            let sample = view.waveform[index] + (view.waveform[index + 1] - view.waveform[index]) * (position - index as f32);
+            let wave_y = view.size.y * (0.5 - sample.clamp(-0.5, 0.5));
+            let line = fill((local.y - wave_y).abs() - 0.75);
+            color = color.lerp(view.colors[PALETTE_COLORS - 1].to_vec3().lerp(Vec3::ONE, 0.6), line);
+            coverage = coverage.max(line * 0.9);
+        }
+
+        let mask = if view.capsule > 0.5 {
+            fill(sd_capsule_box(local - view.size * 0.5, (view.size.x - view.size.y) * 0.5, view.size.y * 0.5)) * 0.35
+        } else {
+            smoothstep(0.0, EDGE_FADE, local.x) * smoothstep(view.size.x, view.size.x - EDGE_FADE, local.x) * 0.6
+        };
+        let alpha = coverage * mask * view.alpha;
+        if alpha <= 0.0 {
+            kill();
+        }
+        (color * alpha).extend(alpha)
+    }
+}
@@ -0,0 +1,314 @@
+use crate::app::{
+    Background, CantusApp, config,
+    platform::{Current as Platform, Platform as _},
+    update,
+};
+use jiff::{SignedDuration, Span, Timestamp, Zoned, civil::Time};
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt::{self, Write},
+    fs, mem,
+    time::Duration,
+};
+use tokio::time::sleep;
+use tracing::warn;
+
+const TIMERS_FILE: &str = "cantus_timers.json";
+/// How long a finished timer flashes in the clock pill.
+const FLASH_SECONDS: f32 = 10.0;
+/// Longest single wait for a timer, since the monotonic clock stops while the machine is suspended.
+const MAX_SLEEP: Duration = Duration::from_secs(30);
+
+#[derive(Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum TimerKind {
+    Countdown,
+    Alarm,
+    Stopwatch,
+}
+
+#[derive(Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Timer {
+    pub kind: TimerKind,
+    /// When a countdown or alarm goes off, or when a stopwatch started.
+    pub at: Timestamp,
+    /// What was asked for, such as `30m` or `07:30`.
+    pub label: String,
+}
+
+impl Timer {
+    /// Reads a launcher command such as `timer 30m`, `alarm 7:30pm` or `stopwatch`, returning the kind and its argument.
+    pub fn command(query: &str) -> Option<(TimerKind, &str)> {
+        let (keyword, argument) = query.split_once(char::is_whitespace).unwrap_or((query, ""));
+        let kind = match keyword.to_ascii_lowercase().as_str() {
+            "timer" => TimerKind::Countdown,
+            "alarm" => TimerKind::Alarm,
+            "stopwatch" => TimerKind::Stopwatch,
+            _ => return None,
+        };
+        Some((kind, argument.trim()))
+    }
+
+    /// A timer started now from a command's argument: a duration (bare numbers are minutes), a wall-clock time, or nothing for a stopwatch.
+    pub fn parse(kind: TimerKind, argument: &str) -> Option<Self> {
+        Self::parse_at(kind, argument, &Zoned::now())
+    }
+
+    fn parse_at(kind: TimerKind, argument: &str, now: &Zoned) -> Option<Self> {
+        let (at, label) = match kind {
+            TimerKind::Countdown => {
+                let duration = argument
+                    .parse::<i64>()
+                    .map(SignedDuration::from_mins)
+                    .or_else(|_| argument.parse::<SignedDuration>())
+                    .ok()
+                    .filter(SignedDuration::is_positive)?;
+                (now.timestamp().checked_add(duration).ok()?, format!("{duration:#}"))
+            }
+            TimerKind::Alarm => {
+                let time = parse_time(argument)?;
+                let mut at = now.with().time(time).build().ok()?;
+                if at <= *now {
+                    at = at.checked_add(Span::new().days(1)).ok()?;
+                }
+                (at.timestamp(), time.strftime("%H:%M").to_string())
+            }
+            TimerKind::Stopwatch if argument.is_empty() => (now.timestamp(), String::new()),
+            TimerKind::Stopwatch => return None,
+        };
+        Some(Self { kind, at, label })
+    }
+
+    pub fn name(&self) -> String {
+        match self.kind {
+            TimerKind::Countdown => format!("{} timer", self.label),
+            TimerKind::Alarm => format!("{} alarm", self.label),
+            TimerKind::Stopwatch => String::from("Stopwatch"),
+        }
+    }
+
+    /// Time left on a countdown or alarm, or time elapsed on a stopwatch, as `h:mm:ss`.
+    pub(crate) fn write_remaining(&self, now: Timestamp, out: &mut impl Write) -> fmt::Result {
+        let seconds = if self.kind == TimerKind::Stopwatch {
+            self.at.duration_until(now).as_secs()
+        } else {
+            // Rounded up, so a countdown reads 0:01 rather than 0:00 in its last second.
+            now.duration_until(self.at).as_secs_f64().ceil() as i64
+        }
+        .max(0);
+        let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+        if hours > 0 {
+            write!(out, "{hours}:{minutes:02}:{seconds:02}")
+        } else {
+            write!(out, "{minutes}:{seconds:02}")
+        }
+    }
+
+    /// Short reading for the clock pill, such as `Timer 29:41` or `Alarm 07:30`.
+    pub(crate) fn write_reading(&self, now: Timestamp, out: &mut impl Write) -> fmt::Result {
+        match self.kind {
+            TimerKind::Countdown => out.write_str("Timer ")?,
+            TimerKind::Alarm => return write!(out, "Alarm {}", self.label),
+            TimerKind::Stopwatch => out.write_str("Stopwatch ")?,
+        }
+        self.write_remaining(now, out)
+    }
+}
+
+/// Accepts `7`, `7:30`, `19:30`, `7pm` and `7:30am`.
+fn parse_time(text: &str) -> Option<Time> {
+    let text = text.to_ascii_lowercase();
+    let (clock, meridiem) = match (text.strip_suffix("am"), text.strip_suffix("pm")) {
+        (Some(clock), _) => (clock, Some(0)),
+        (_, Some(clock)) => (clock, Some(12)),
+        _ => (text.as_str(), None),
+    };
+    let (hour, minute) = clock.trim().split_once(':').unwrap_or((clock.trim(), "0"));
+    let (hour, minute) = (hour.parse::<i8>().ok()?, minute.parse::<i8>().ok()?);
+    let hour = match meridiem {
+        Some(offset) if (1..=12).contains(&hour) => hour % 12 + offset,
+        Some(_) => return None,
+        None => hour,
+    };
+    Time::new(hour, minute, 0, 0).ok()
+}
+
+/// Running countdowns, alarms and stopwatches, saved so they survive a restart.
+pub struct Timers {
+    pub active: Vec<Timer>,
+    /// The latest timer to go off, and when.
+    finished: Option<(Timer, Timestamp)>,
+    background: Background,
+}
+
+impl Timers {
+    pub fn load(background: &Background) -> Self {
+        let active = fs::read(config::directory().join(TIMERS_FILE))
+            .ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).inspect_err(|error| warn!(%error, "Failed to parse timers")).ok())
+            .unwrap_or_default();
+        let timers = Self {
+            active,
+            finished: None,
+            background: background.clone(),
+        };
+        // Anything that went off while Cantus wasn't running fires straight away.
+        for timer in &timers.active {
+            timers.schedule(timer);
+        }
+        timers
+    }
+
+    pub fn start(&mut self, timer: Timer) {
+        self.schedule(&timer);
+        self.active.push(timer);
+        self.save();
+    }
+
+    pub fn cancel(&mut self, index: usize) -> Timer {
+        let timer = self.active.remove(index);
+        self.save();
+        timer
+    }
+
+    /// The timer that just went off and how strongly it still flashes, fading from 1 to 0.
+    pub fn flash(&self, now: Timestamp) -> Option<(&Timer, f32)> {
+        let (timer, at) = self.finished.as_ref()?;
+        let elapsed = at.duration_until(now).as_secs_f32();
+        (elapsed < FLASH_SECONDS).then_some((timer, 1.0 - elapsed / FLASH_SECONDS))
+    }
+
+    /// Wakes once `timer` is due to fire it; stopwatches never are.
+    fn schedule(&self, timer: &Timer) {
+        if timer.kind == TimerKind::Stopwatch {
+            return;
+        }
+        let at = timer.at;
+        self.background.spawn(async move {
+            while let Ok(wait) = Duration::try_from(Timestamp::now().duration_until(at)) {
+                if wait.is_zero() {
+                    break;
+                }
+                sleep(wait.min(MAX_SLEEP)).await;
+            }
+            Some(update(CantusApp::expire_timers))
+        });
+    }
+
+    /// Removes every countdown and alarm that is due, notifying for each.
+    fn expire(&mut self) {
+        let now = Timestamp::now();
+        let (due, active): (Vec<_>, _) = mem::take(&mut self.active)
+            .into_iter()
+            .partition(|timer| timer.kind != TimerKind::Stopwatch && timer.at <= now);
+        self.active = active;
+        for timer in &due {
+            let body = match timer.kind {
+                TimerKind::Alarm => format!("It's {}", timer.label),
+                _ => format!("{} has passed", timer.label),
+            };
+            Platform::notify(&self.background, &format!("{} finished", timer.name()), &body, timer.kind == TimerKind::Alarm);
+        }
+        if let Some(timer) = due.into_iter().last() {
+            self.finished = Some((timer, now));
+            self.save();
+        }
+    }
+
+    fn save(&self) {
+        let Ok(contents) = serde_json::to_vec(&self.active) else {
+            return;
+        };
+        self.background.run(move || {
+            if let Err(error) = fs::write(config::directory().join(TIMERS_FILE), contents) {
+                warn!(%error, "Failed to save timers");
+            }
+        });
+    }
+}
+
+impl CantusApp {
+    /// Starts the timer picked in the launcher, or stops it when it is running; a stopped stopwatch copies its reading.
+    pub(crate) fn apply_launcher_timer(&mut self) {
+        let Some((timer, running)) = self.launcher.pending_timer.take() else {
+            return;
+        };
+        if running {
+            // The row is a snapshot, so the timer may have gone off since it was listed.
+            let Some(index) = self.timers.active.iter().position(|active| *active == timer) else {
+                return;
+            };
+            let timer = self.timers.cancel(index);
+            if timer.kind == TimerKind::Stopwatch {
+                let mut elapsed = String::new();
+                timer.write_remaining(Timestamp::now(), &mut elapsed).unwrap();
+                self.launcher.pending_copy = Some(elapsed);
+            }
+        } else {
+            self.timers.start(timer);
+        }
+        self.launcher.set_timers(&self.timers.active);
+    }
+
+    fn expire_timers(&mut self) {
+        self.timers.expire();
+        self.launcher.set_timers(&self.timers.active);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jiff::{civil::time, tz::TimeZone};
+
+    /// 20:00 UTC, the time every test parses against.
+    fn evening() -> Zoned {
+        jiff::civil::date(2026, 10, 18).at(20, 0, 0, 0).to_zoned(TimeZone::UTC).unwrap()
+    }
+
+    /// How far after [`evening`] a timer parsed from `argument` goes off.
+    fn delay(kind: TimerKind, argument: &str) -> Option<SignedDuration> {
+        let now = evening();
+        Timer::parse_at(kind, argument, &now).map(|timer| now.timestamp().duration_until(timer.at))
+    }
+
+    #[test]
+    fn commands_name_their_kind() {
+        assert!(Timer::command("timer 30m") == Some((TimerKind::Countdown, "30m")));
+        assert!(Timer::command("Alarm  7:30pm ") == Some((TimerKind::Alarm, "7:30pm")));
+        assert!(Timer::command("stopwatch") == Some((TimerKind::Stopwatch, "")));
+        assert!(Timer::command("timers 5").is_none());
+    }
+
+    #[test]
+    fn times_read_both_clocks() {
+        assert_eq!(parse_time("12am"), Some(time(0, 0, 0, 0)));
+        assert_eq!(parse_time("12pm"), Some(time(12, 0, 0, 0)));
+        assert_eq!(parse_time("7:30PM"), Some(time(19, 30, 0, 0)));
+        assert_eq!(parse_time("19:30"), Some(time(19, 30, 0, 0)));
+        assert_eq!(parse_time("7"), Some(time(7, 0, 0, 0)));
+        assert_eq!(parse_time("0pm"), None);
+        assert_eq!(parse_time("13am"), None);
+        assert_eq!(parse_time("25:00"), None);
+    }
+
+    #[test]
+    fn countdowns_take_bare_minutes_or_durations() {
+        assert_eq!(delay(TimerKind::Countdown, "5"), Some(SignedDuration::from_mins(5)));
+        assert_eq!(delay(TimerKind::Countdown, "1h30m"), Some(SignedDuration::from_mins(90)));
+        assert_eq!(delay(TimerKind::Countdown, "0"), None);
+        assert_eq!(delay(TimerKind::Countdown, "soon"), None);
+    }
+
+    #[test]
+    fn alarms_already_passed_ring_tomorrow() {
+        assert_eq!(delay(TimerKind::Alarm, "9pm"), Some(SignedDuration::from_hours(1)));
+        assert_eq!(delay(TimerKind::Alarm, "7pm"), Some(SignedDuration::from_hours(23)));
+        assert_eq!(delay(TimerKind::Alarm, "20:00"), Some(SignedDuration::from_hours(24)));
+    }
+
+    #[test]
+    fn stopwatches_take_no_argument() {
+        assert_eq!(delay(TimerKind::Stopwatch, ""), Some(SignedDuration::ZERO));
+        assert_eq!(delay(TimerKind::Stopwatch, "foo"), None);
+    }
+}
@@ -60,6 +60,35 @@
     default = builtins.fromJSON "true";
     description = "Whether to show synchronized lyrics.";
   };
+  lyrics_providers = lib.mkOption {
+    type = lib.types.listOf (lib.types.enum [
+      "local"
+      "binimum"
+      "lrclib"
+      "spotify"
+    ]);
+    default = builtins.fromJSON "[\"local\",\"binimum\",\"lrclib\",\"spotify\"]";
+    description = "Lyrics sources, tried in order until one has timed lyrics for the track: `local` `.lrc` files, `binimum`, `lrclib` or `spotify`.";
+  };
+  lyrics_directories = lib.mkOption {
+    type = lib.types.listOf (lib.types.str);
+    default = builtins.fromJSON "[\"~/Music\",\"~/.lyrics\"]";
+    description = "Directories searched, a few levels deep, for `Artist - Title.lrc` or `Title.lrc` files.";
+  };
+  lyrics_annotation = lib.mkOption {
+    type = lib.types.enum [
+      "off"
+      "translation"
+      "romanization"
+    ];
+    default = builtins.fromJSON "\"off\"";
+    description = "Whether to show a `translation` or `romanization` row beneath lyrics that include one.";
+  };
+  player_application = lib.mkOption {
+    type = lib.types.str;
+    default = builtins.fromJSON "\"spotify\"";
+    description = "Application name or process binary of the music player, whose own audio stream scrolling a track adjusts. Media from other MPRIS players shows as an interruption at the playhead.";
+  };
   tempestas_enabled = lib.mkOption {
     type = lib.types.bool;
     default = builtins.fromJSON "true";
@@ -75,9 +104,73 @@
     default = builtins.fromJSON "true";
     description = "Whether to show the system status module.";
   };
+  visualiser = lib.mkOption {
+    type = lib.types.enum [
+      "off"
+      "pill"
+      "strip"
+    ];
+    default = builtins.fromJSON "\"off\"";
+    description = "Where to draw a detailed audio spectrum: `off`, across the playing track's `pill`, or full width along the lyrics `strip`.";
+  };
+  visualiser_bands = lib.mkOption {
+    type = lib.types.int;
+    default = builtins.fromJSON "64";
+    description = "Number of logarithmically spaced frequency bands in the visualiser, up to 128.";
+  };
+  visualiser_smoothing = lib.mkOption {
+    type = lib.types.number;
+    default = builtins.fromJSON "0.12";
+    description = "Seconds the visualiser takes to settle after a change; higher is smoother.";
+  };
+  visualiser_waveform = lib.mkOption {
+    type = lib.types.bool;
+    default = builtins.fromJSON "false";
+    description = "Whether to also trace the waveform through the visualiser.";
+  };
+  palette = lib.mkOption {
+    type = lib.types.attrs;
+    default = builtins.fromJSON "{\"min_spread\":30,\"min_chroma\":0.0,\"lightness\":[0.0,100.0],\"harmony\":true}";
+    description = "How colours are picked from album art.";
+  };
   search_providers = lib.mkOption {
     type = lib.types.listOf (lib.types.attrs);
     default = builtins.fromJSON "[{\"name\":\"DuckDuckGo\",\"url\":\"https://duckduckgo.com/?q={searchTerms}\",\"alias\":\"!ddg\"}]";
     description = "Web search providers; the first is the unprefixed fallback.";
   };
+  launcher_commands = lib.mkOption {
+    type = lib.types.listOf (lib.types.attrs);
+    default = builtins.fromJSON "[]";
+    description = "Commands whose printed rows join the launcher's results.";
+  };
+  terminal = lib.mkOption {
+    type = lib.types.nullOr (lib.types.str);
+    default = builtins.fromJSON "null";
+    description = "Command that terminal apps run in, with `{cmd}` standing for the app's command, such as `foot -e {cmd}`; `$TERMINAL` or the first common terminal installed is used when unset.";
+  };
+  systemd_scopes = lib.mkOption {
+    type = lib.types.bool;
+    default = builtins.fromJSON "false";
+    description = "Whether the launcher starts each app in its own systemd user scope, so it is tracked apart from Cantus.";
+  };
+  clipboard_history = lib.mkOption {
+    type = lib.types.int;
+    default = builtins.fromJSON "50";
+    description = "Copied texts kept for the launcher's `!clip` mode; 0 stops watching the clipboard.";
+  };
+  clipboard_history_persist = lib.mkOption {
+    type = lib.types.bool;
+    default = builtins.fromJSON "false";
+    description = "Whether the clipboard history is saved across restarts.";
+  };
+  fallback_font = lib.mkOption {
+    type = lib.types.nullOr (lib.types.str);
+    default = builtins.fromJSON "null";
+    description = "Outline font drawing emoji and symbols the built-in font lacks; a system emoji font is used when unset.";
+  };
+  services = lib.mkOption {
+    type = lib.types.attrs;
+    default = builtins.fromJSON "{\"reccobeats\":\"https://api.reccobeats.com/v1\",\"binimum\":\"https://lyrics-api.binimum.org\",\"lrclib\":\"https://lrclib.net\",\"open_meteo\":\"https://api.open-meteo.com/v1\",\"open_meteo_geocoding\":\"https://geocoding-api.open-meteo.com/v1\",\"exchange_rates\":\"https://open.er-api.com/v6\",\"musicbrainz\":\"\",\"musicbrainz_requests_per_second\":1.0}";
+    description = "Base URLs of the online services used, for self-hosted mirrors or local stand-ins; an empty URL disables that service.";
+  };
 }